@@ -0,0 +1,106 @@
+/// An index of vocabulary words that resolves abbreviations (prefixes) of them
+///
+/// Lookups are ASCII-case-insensitive.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Abbreviations<'a, T> {
+    // Sorted by the first element, the word folded to lowercase
+    entries: Vec<(String, &'a str, T)>,
+    min_length: usize,
+}
+
+impl<'a, T: Copy + Eq> Abbreviations<'a, T> {
+    /// Construct an index of the given words and the values they map to.
+    /// By default, abbreviations of any length are expanded; see
+    /// [`Abbreviations::min_length()`].
+    pub fn new<I>(words: I) -> Self
+    where
+        I: IntoIterator<Item = (&'a str, T)>,
+    {
+        let mut entries = words
+            .into_iter()
+            .map(|(w, value)| (w.to_ascii_lowercase(), w, value))
+            .collect::<Vec<_>>();
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        Abbreviations {
+            entries,
+            min_length: 1,
+        }
+    }
+
+    /// Set the minimum length of an abbreviation.  Abbreviations shorter than
+    /// `min_length` characters will not be expanded.  Defaults to 1.
+    pub fn min_length(mut self, min_length: usize) -> Self {
+        self.min_length = min_length;
+        self
+    }
+
+    /// Look up the words that `prefix` is an abbreviation of.  If all such
+    /// words map to the same value, that value is returned.
+    pub fn expand(&self, prefix: &str) -> Expansion<'a, T> {
+        if prefix.chars().count() < self.min_length {
+            return Expansion::Unknown;
+        }
+        let prefix = prefix.to_ascii_lowercase();
+        let start = self.entries.partition_point(|(w, _, _)| *w < prefix);
+        let mut found: Vec<(&'a str, T)> = Vec::new();
+        for (w, original, value) in &self.entries[start..] {
+            if !w.starts_with(&prefix) {
+                break;
+            }
+            if !found.iter().any(|(_, v)| v == value) {
+                found.push((original, *value));
+            }
+        }
+        match found.as_slice() {
+            [] => Expansion::Unknown,
+            [(_, value)] => Expansion::Unique(*value),
+            _ => Expansion::Ambiguous(found.into_iter().map(|(w, _)| w).collect()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Expansion<'a, T> {
+    /// The abbreviation unambiguously refers to the given value
+    Unique(T),
+    /// The abbreviation could refer to any of the given words, each of which
+    /// maps to a different value
+    Ambiguous(Vec<&'a str>),
+    /// The abbreviation is too short or does not match any words
+    Unknown,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand() {
+        let words = [
+            ("EAST", 1),
+            ("E", 1),
+            ("EASEL", 2),
+            ("BOOK", 3),
+            ("BOOKS", 3),
+            ("BANQUET", 4),
+        ];
+        let abbrevs = Abbreviations::new(words).min_length(3);
+        assert_eq!(abbrevs.expand("boo"), Expansion::Unique(3));
+        assert_eq!(abbrevs.expand("BOOKS"), Expansion::Unique(3));
+        assert_eq!(abbrevs.expand("Ban"), Expansion::Unique(4));
+        assert_eq!(abbrevs.expand("EAST"), Expansion::Unique(1));
+        assert_eq!(
+            abbrevs.expand("EAS"),
+            Expansion::Ambiguous(vec!["EASEL", "EAST"])
+        );
+        assert_eq!(abbrevs.expand("BA"), Expansion::Unknown);
+        assert_eq!(abbrevs.expand("BOOKSHELF"), Expansion::Unknown);
+        assert_eq!(abbrevs.expand("CAT"), Expansion::Unknown);
+        let abbrevs = Abbreviations::new(words);
+        assert_eq!(abbrevs.expand("BA"), Expansion::Unique(4));
+        assert_eq!(
+            abbrevs.expand("b"),
+            Expansion::Ambiguous(vec!["BANQUET", "BOOK"])
+        );
+    }
+}
@@ -0,0 +1,232 @@
+use crate::interface::{Interface, InterfaceBuilder, OutputContext};
+use crate::{Message, join_messages};
+use std::future::Future;
+use std::io;
+
+/// The asynchronous counterpart of [`InterfaceBuilder`]
+pub trait AsyncInterfaceBuilder: Sized {
+    type Interface: AsyncInterface;
+
+    fn build_interface(self) -> impl Future<Output = io::Result<Self::Interface>> + Send;
+}
+
+/// The asynchronous counterpart of [`Interface`], for use with
+/// [`run_game_async()`](crate::run_game_async)
+pub trait AsyncInterface: Send {
+    /// Display the given text in the interface.
+    fn show_output(&mut self, text: &str) -> impl Future<Output = io::Result<()>> + Send;
+
+    /// Display the given text in the interface along with details about the
+    /// state of the game.  The default implementation ignores the details and
+    /// calls [`AsyncInterface::show_output()`].
+    fn show_output_with_context(
+        &mut self,
+        text: &str,
+        ctx: &OutputContext,
+    ) -> impl Future<Output = io::Result<()>> + Send {
+        let _ = ctx;
+        self.show_output(text)
+    }
+
+    /// Display a game's output, made up of `messages`, along with details
+    /// about the state of the game.  The default implementation joins the
+    /// messages' text together with newlines and calls
+    /// [`AsyncInterface::show_output_with_context()`].
+    fn show_messages(
+        &mut self,
+        messages: &[Message],
+        ctx: &OutputContext,
+    ) -> impl Future<Output = io::Result<()>> + Send {
+        async move {
+            self.show_output_with_context(&join_messages(messages), ctx)
+                .await
+        }
+    }
+
+    /// Read a line of input from the interface.
+    ///
+    /// Returns `None` on end of input.
+    fn get_input(&mut self) -> impl Future<Output = io::Result<Option<String>>> + Send;
+}
+
+/// An adapter for using a synchronous [`InterfaceBuilder`] where an
+/// [`AsyncInterfaceBuilder`] is expected
+///
+/// The resulting interface performs its I/O synchronously, blocking the
+/// executor thread while it waits for input, so it is only suitable for
+/// running a single game, such as a local game on the terminal.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BlockingInterfaceBuilder<B>(pub B);
+
+impl<B> AsyncInterfaceBuilder for BlockingInterfaceBuilder<B>
+where
+    B: InterfaceBuilder + Send,
+    B::Interface: Send,
+{
+    type Interface = BlockingInterface<B::Interface>;
+
+    async fn build_interface(self) -> io::Result<Self::Interface> {
+        self.0.build_interface().map(BlockingInterface)
+    }
+}
+
+/// An [`AsyncInterface`] that wraps a synchronous [`Interface`]; see
+/// [`BlockingInterfaceBuilder`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BlockingInterface<I>(pub I);
+
+impl<I: Interface + Send> AsyncInterface for BlockingInterface<I> {
+    async fn show_output(&mut self, text: &str) -> io::Result<()> {
+        self.0.show_output(text)
+    }
+
+    async fn show_output_with_context(
+        &mut self,
+        text: &str,
+        ctx: &OutputContext,
+    ) -> io::Result<()> {
+        self.0.show_output_with_context(text, ctx)
+    }
+
+    async fn show_messages(&mut self, messages: &[Message], ctx: &OutputContext) -> io::Result<()> {
+        self.0.show_messages(messages, ctx)
+    }
+
+    async fn get_input(&mut self) -> io::Result<Option<String>> {
+        self.0.get_input()
+    }
+}
+
+#[cfg(feature = "tokio")]
+pub use self::tokio_interface::*;
+
+#[cfg(feature = "tokio")]
+mod tokio_interface {
+    use super::{AsyncInterface, AsyncInterfaceBuilder};
+    use std::io;
+    use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt};
+
+    /// The asynchronous counterpart of
+    /// [`BasicInterfaceBuilder`](crate::BasicInterfaceBuilder), using Tokio's
+    /// I/O traits
+    #[derive(Clone, Debug, Eq, PartialEq)]
+    pub struct AsyncBasicInterfaceBuilder<R, W> {
+        reader: R,
+        writer: W,
+    }
+
+    impl<R, W> AsyncBasicInterfaceBuilder<R, W> {
+        pub fn new(reader: R, writer: W) -> Self {
+            AsyncBasicInterfaceBuilder { reader, writer }
+        }
+    }
+
+    impl<R, W> AsyncInterfaceBuilder for AsyncBasicInterfaceBuilder<R, W>
+    where
+        R: AsyncBufRead + Unpin + Send,
+        W: AsyncWrite + Unpin + Send,
+    {
+        type Interface = AsyncBasicInterface<R, W>;
+
+        async fn build_interface(self) -> io::Result<Self::Interface> {
+            Ok(AsyncBasicInterface {
+                reader: self.reader,
+                writer: self.writer,
+                wrote_prompt: false,
+                wrote_last_output: false,
+            })
+        }
+    }
+
+    #[derive(Clone, Debug, Eq, PartialEq)]
+    pub struct AsyncBasicInterface<R, W> {
+        reader: R,
+        writer: W,
+        wrote_prompt: bool,
+        wrote_last_output: bool,
+    }
+
+    impl<R, W> AsyncInterface for AsyncBasicInterface<R, W>
+    where
+        R: AsyncBufRead + Unpin + Send,
+        W: AsyncWrite + Unpin + Send,
+    {
+        async fn show_output(&mut self, text: &str) -> io::Result<()> {
+            if self.wrote_prompt {
+                self.writer.write_all(b"\n").await?;
+            }
+            if !text.is_empty() {
+                self.writer.write_all(text.as_bytes()).await?;
+                self.writer.write_all(b"\n").await?;
+                self.wrote_last_output = true;
+            } else {
+                self.wrote_last_output = false;
+            }
+            self.writer.flush().await
+        }
+
+        async fn get_input(&mut self) -> io::Result<Option<String>> {
+            if self.wrote_last_output {
+                self.writer.write_all(b"\n").await?;
+            }
+            self.writer.write_all(b"> ").await?;
+            self.writer.flush().await?;
+            self.wrote_prompt = true;
+            let mut input = String::new();
+            if self.reader.read_line(&mut input).await? != 0 {
+                Ok(Some(input))
+            } else {
+                // Force the start of a new line:
+                self.writer.write_all(b"\n").await?;
+                self.writer.flush().await?;
+                Ok(None)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::Counter;
+    use crate::{BasicInterfaceBuilder, run_game_async};
+    use std::pin::pin;
+    use std::task::{Context, Poll, Waker};
+
+    // Run a future that never has to wait to completion
+    fn block_on<F: Future>(fut: F) -> F::Output {
+        let mut fut = pin!(fut);
+        let mut cx = Context::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(r) = fut.as_mut().poll(&mut cx) {
+                return r;
+            }
+        }
+    }
+
+    #[test]
+    fn test_blocking() {
+        let mut output = Vec::new();
+        let iface = BlockingInterfaceBuilder(BasicInterfaceBuilder::new(
+            &b"one\ntwo\nstop\nthree\n"[..],
+            &mut output,
+        ));
+        block_on(run_game_async(iface, Counter(0))).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "Count!\n\n> \n1\n\n> \n2\n\n> \nFinal count: 2\n"
+        );
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn test_tokio() {
+        let mut output = Vec::new();
+        let iface = AsyncBasicInterfaceBuilder::new(&b"one\ntwo\n"[..], &mut output);
+        block_on(run_game_async(iface, Counter(0))).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "Count!\n\n> \n1\n\n> \n2\n\n> \n"
+        );
+    }
+}
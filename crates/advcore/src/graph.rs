@@ -0,0 +1,246 @@
+use std::fmt::{self, Write};
+
+/// A directed graph of a game's rooms and the exits between them, for
+/// exporting to graph-drawing tools
+///
+/// Rooms are identified by IDs, which should consist only of ASCII letters,
+/// digits, and underscores.  An exit with no exit leading back the other way
+/// is considered one-way and is highlighted in the output.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct WorldGraph {
+    nodes: Vec<Node>,
+    edges: Vec<Edge>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Node {
+    id: String,
+    label: String,
+    notes: Vec<String>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Edge {
+    from: String,
+    to: String,
+    label: String,
+}
+
+impl WorldGraph {
+    pub fn new() -> Self {
+        WorldGraph::default()
+    }
+
+    /// Add a room with the given ID and display label.  Adding a room with an
+    /// ID that is already present replaces its label.
+    pub fn add_node(&mut self, id: &str, label: &str) {
+        if let Some(node) = self.node_mut(id) {
+            label.clone_into(&mut node.label);
+        } else {
+            self.nodes.push(Node {
+                id: id.to_owned(),
+                label: label.to_owned(),
+                notes: Vec::new(),
+            });
+        }
+    }
+
+    /// Attach a note (such as the name of an object in the room) to the room
+    /// with the given ID, adding the room if it is not already present
+    pub fn annotate(&mut self, id: &str, note: &str) {
+        if self.node_mut(id).is_none() {
+            self.add_node(id, id);
+        }
+        if let Some(node) = self.node_mut(id) {
+            node.notes.push(note.to_owned());
+        }
+    }
+
+    /// Add an exit from one room to another, labelled with the direction
+    pub fn add_edge(&mut self, from: &str, to: &str, label: &str) {
+        self.edges.push(Edge {
+            from: from.to_owned(),
+            to: to.to_owned(),
+            label: label.to_owned(),
+        });
+    }
+
+    pub fn render(&self, format: GraphFormat) -> String {
+        match format {
+            GraphFormat::Dot => self.to_dot(),
+            GraphFormat::Mermaid => self.to_mermaid(),
+        }
+    }
+
+    /// Render the graph in the Graphviz DOT language
+    pub fn to_dot(&self) -> String {
+        let mut s = String::from("digraph {\n");
+        for node in &self.nodes {
+            let label = std::iter::once(&node.label)
+                .chain(&node.notes)
+                .map(|t| dot_escape(t))
+                .collect::<Vec<_>>()
+                .join("\\n");
+            let _ = writeln!(s, "    {} [label=\"{label}\"];", node.id);
+        }
+        for edge in &self.edges {
+            let _ = write!(
+                s,
+                "    {} -> {} [label=\"{}\"",
+                edge.from,
+                edge.to,
+                dot_escape(&edge.label)
+            );
+            if self.is_one_way(edge) {
+                s.push_str(", color=red, style=dashed");
+            }
+            s.push_str("];\n");
+        }
+        s.push('}');
+        s
+    }
+
+    /// Render the graph as a Mermaid flowchart
+    pub fn to_mermaid(&self) -> String {
+        let mut s = String::from("flowchart TD\n");
+        for node in &self.nodes {
+            let label = std::iter::once(&node.label)
+                .chain(&node.notes)
+                .map(|t| mermaid_escape(t))
+                .collect::<Vec<_>>()
+                .join("<br/>");
+            let _ = writeln!(s, "    {}[\"{label}\"]", node.id);
+        }
+        let mut one_way = Vec::new();
+        for (i, edge) in self.edges.iter().enumerate() {
+            let arrow = if self.is_one_way(edge) {
+                one_way.push(i.to_string());
+                "-.->"
+            } else {
+                "-->"
+            };
+            let _ = writeln!(
+                s,
+                "    {} {arrow}|\"{}\"| {}",
+                edge.from,
+                mermaid_escape(&edge.label),
+                edge.to
+            );
+        }
+        if !one_way.is_empty() {
+            let _ = writeln!(s, "    linkStyle {} stroke:red", one_way.join(","));
+        }
+        if s.ends_with('\n') {
+            s.pop();
+        }
+        s
+    }
+
+    fn node_mut(&mut self, id: &str) -> Option<&mut Node> {
+        self.nodes.iter_mut().find(|n| n.id == id)
+    }
+
+    fn is_one_way(&self, edge: &Edge) -> bool {
+        !self
+            .edges
+            .iter()
+            .any(|e| e.from == edge.to && e.to == edge.from)
+    }
+}
+
+/// The output formats supported by [`WorldGraph::render()`]
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum GraphFormat {
+    Dot,
+    Mermaid,
+}
+
+impl std::str::FromStr for GraphFormat {
+    type Err = ParseGraphFormatError;
+
+    fn from_str(s: &str) -> Result<GraphFormat, ParseGraphFormatError> {
+        match s.to_ascii_lowercase().as_str() {
+            "dot" | "graphviz" => Ok(GraphFormat::Dot),
+            "mermaid" => Ok(GraphFormat::Mermaid),
+            _ => Err(ParseGraphFormatError(s.to_owned())),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParseGraphFormatError(String);
+
+impl fmt::Display for ParseGraphFormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid graph format {:?}; expected \"dot\" or \"mermaid\"",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for ParseGraphFormatError {}
+
+fn dot_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn mermaid_escape(s: &str) -> String {
+    s.replace('"', "#quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> WorldGraph {
+        let mut graph = WorldGraph::new();
+        graph.add_node("Hall", "Great Hall");
+        graph.add_node("Attic", "Attic");
+        graph.annotate("Hall", "a \"grand\" piano");
+        graph.add_edge("Hall", "Attic", "up");
+        graph.add_edge("Attic", "Hall", "down");
+        graph.add_edge("Attic", "Pit", "jump");
+        graph
+    }
+
+    #[test]
+    fn test_to_dot() {
+        assert_eq!(
+            sample().render(GraphFormat::Dot),
+            concat!(
+                "digraph {\n",
+                "    Hall [label=\"Great Hall\\na \\\"grand\\\" piano\"];\n",
+                "    Attic [label=\"Attic\"];\n",
+                "    Hall -> Attic [label=\"up\"];\n",
+                "    Attic -> Hall [label=\"down\"];\n",
+                "    Attic -> Pit [label=\"jump\", color=red, style=dashed];\n",
+                "}",
+            )
+        );
+    }
+
+    #[test]
+    fn test_to_mermaid() {
+        assert_eq!(
+            sample().render(GraphFormat::Mermaid),
+            concat!(
+                "flowchart TD\n",
+                "    Hall[\"Great Hall<br/>a #quot;grand#quot; piano\"]\n",
+                "    Attic[\"Attic\"]\n",
+                "    Hall -->|\"up\"| Attic\n",
+                "    Attic -->|\"down\"| Hall\n",
+                "    Attic -.->|\"jump\"| Pit\n",
+                "    linkStyle 2 stroke:red",
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_format() {
+        assert_eq!("dot".parse::<GraphFormat>(), Ok(GraphFormat::Dot));
+        assert_eq!("Mermaid".parse::<GraphFormat>(), Ok(GraphFormat::Mermaid));
+        assert!("svg".parse::<GraphFormat>().is_err());
+    }
+}
@@ -1,5 +1,9 @@
+use crate::{Message, MessageKind, join_messages};
 use rustyline::{DefaultEditor, error::ReadlineError};
+use std::fmt;
 use std::io::{self, BufRead, Write};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
 
 pub trait InterfaceBuilder: Sized {
     type Interface: Interface;
@@ -11,21 +15,108 @@ pub trait Interface {
     /// Display the given text in the interface.
     fn show_output(&mut self, text: &str) -> io::Result<()>;
 
+    /// Display the given text in the interface along with details about the
+    /// state of the game.  The default implementation ignores the details and
+    /// calls [`Interface::show_output()`].
+    fn show_output_with_context(&mut self, text: &str, ctx: &OutputContext) -> io::Result<()> {
+        let _ = ctx;
+        self.show_output(text)
+    }
+
+    /// Display a game's output, made up of `messages`, along with details
+    /// about the state of the game.  Interfaces can override this to display
+    /// different kinds of messages differently.  The default implementation
+    /// joins the messages' text together with newlines and calls
+    /// [`Interface::show_output_with_context()`].
+    fn show_messages(&mut self, messages: &[Message], ctx: &OutputContext) -> io::Result<()> {
+        self.show_output_with_context(&join_messages(messages), ctx)
+    }
+
     /// Read a line of input from the interface.
     ///
-    /// Returns `None` on end of input.
+    /// Returns `None` on end of input.  Interfaces that notice a request to
+    /// terminate the program while waiting for input should return a
+    /// [`Terminated`] error.
     fn get_input(&mut self) -> io::Result<Option<String>>;
+
+    /// Returns the current width of the display in columns, if known.  This
+    /// can change over the course of a game, such as when the player resizes
+    /// their terminal.  The default implementation returns `None`.
+    fn columns(&mut self) -> Option<usize> {
+        None
+    }
+}
+
+/// The error returned by [`Interface::get_input()`] when the program is asked
+/// to terminate (e.g., by SIGTERM or SIGHUP) while waiting for input
+///
+/// [`run_game()`](crate::run_game) responds to this error by calling
+/// [`GameEngine::autosave()`](crate::GameEngine::autosave) before returning
+/// it.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Terminated;
+
+impl Terminated {
+    /// Returns true if `e` was created from a `Terminated`
+    pub fn is(e: &io::Error) -> bool {
+        matches!(e.get_ref(), Some(inner) if inner.is::<Terminated>())
+    }
+}
+
+impl fmt::Display for Terminated {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "terminated by a signal")
+    }
+}
+
+impl std::error::Error for Terminated {}
+
+impl From<Terminated> for io::Error {
+    fn from(e: Terminated) -> io::Error {
+        io::Error::new(io::ErrorKind::Interrupted, e)
+    }
+}
+
+/// Details about the state of a game that accompany a piece of output
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct OutputContext {
+    /// Whether the game has ended, in which case no more input will be read
+    pub ended: bool,
+
+    /// The name of the player's current location, if known; see
+    /// [`GameEngine::location()`](crate::GameEngine::location)
+    pub location: Option<String>,
+
+    /// A summary of the player's progress, if the game provides one; see
+    /// [`GameEngine::status()`](crate::GameEngine::status)
+    pub status: Option<String>,
+
+    /// The number of inputs that the game has handled so far
+    pub turn: u64,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct BasicInterfaceBuilder<R, W> {
     reader: R,
     writer: W,
+    echo: bool,
 }
 
 impl<R, W> BasicInterfaceBuilder<R, W> {
     pub fn new(reader: R, writer: W) -> Self {
-        BasicInterfaceBuilder { reader, writer }
+        BasicInterfaceBuilder {
+            reader,
+            writer,
+            echo: false,
+        }
+    }
+
+    /// Whether to write each line of input to the writer after the prompt, so
+    /// that the output reads like a transcript when the input is not typed by
+    /// a user (e.g., when it comes from a script).  Defaults to false.
+    pub fn echo(mut self, echo: bool) -> Self {
+        self.echo = echo;
+        self
     }
 }
 
@@ -36,6 +127,7 @@ impl<R: BufRead, W: Write> InterfaceBuilder for BasicInterfaceBuilder<R, W> {
         Ok(BasicInterface {
             reader: self.reader,
             writer: self.writer,
+            echo: self.echo,
             wrote_prompt: false,
             wrote_last_output: false,
         })
@@ -46,6 +138,7 @@ impl<R: BufRead, W: Write> InterfaceBuilder for BasicInterfaceBuilder<R, W> {
 pub struct BasicInterface<R, W> {
     reader: R,
     writer: W,
+    echo: bool,
     wrote_prompt: bool,
     wrote_last_output: bool,
 }
@@ -73,6 +166,12 @@ impl<R: BufRead, W: Write> Interface for BasicInterface<R, W> {
         self.wrote_prompt = true;
         let mut input = String::new();
         if self.reader.read_line(&mut input)? != 0 {
+            if self.echo {
+                write!(&mut self.writer, "{input}")?;
+                if !input.ends_with('\n') {
+                    writeln!(&mut self.writer)?;
+                }
+            }
             Ok(Some(input))
         } else {
             // Force the start of a new line:
@@ -93,45 +192,120 @@ impl InterfaceBuilder for StandardInterfaceBuilder {
     }
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
-pub struct ReadlineInterfaceBuilder;
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ReadlineInterfaceBuilder {
+    color: bool,
+}
+
+impl ReadlineInterfaceBuilder {
+    pub fn new() -> Self {
+        ReadlineInterfaceBuilder::default()
+    }
+
+    /// Whether to highlight the prompt using ANSI color codes.  Defaults to
+    /// false.
+    pub fn color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+}
 
 impl InterfaceBuilder for ReadlineInterfaceBuilder {
     type Interface = ReadlineInterface;
 
     fn build_interface(self) -> io::Result<Self::Interface> {
-        ReadlineInterface::new()
+        ReadlineInterface::new(self.color)
     }
 }
 
+/// An interface that reads input from the terminal with line editing and
+/// history
+///
+/// Pressing Ctrl-C discards the line being edited; pressing it again asks the
+/// player whether they really want to quit.  On Unix, Ctrl-C pressed while not
+/// reading input counts as the first of those presses, and SIGTERM and SIGHUP
+/// cause [`Interface::get_input()`] to return a [`Terminated`] error so that
+/// the game can be saved before the program exits.
 #[derive(Debug)]
 pub struct ReadlineInterface {
     rl: DefaultEditor,
     stdout: io::StdoutLock<'static>,
+    color: bool,
+    prompt: &'static str,
     wrote_prompt: bool,
     wrote_last_output: bool,
+    // Whether Ctrl-C was pressed since the last line of input
+    interrupted: bool,
+    // One of the `TERMINATION_*` states, shared with the thread started by
+    // `watch_for_termination()`
+    termination: Arc<AtomicU8>,
+    // Set by that thread when SIGINT is received while not reading input
+    stray_interrupt: Arc<AtomicBool>,
+    #[cfg(unix)]
+    signals: signal_hook::iterator::Handle,
 }
 
 impl ReadlineInterface {
-    fn new() -> io::Result<Self> {
-        let cfg = rustyline::config::Builder::new()
-            .auto_add_history(true)
-            .build();
-        let rl = match DefaultEditor::with_config(cfg) {
-            Ok(rl) => rl,
-            Err(ReadlineError::Io(e)) => return Err(e),
-            #[cfg(unix)]
-            Err(ReadlineError::Errno(e)) => return Err(e.into()),
-            Err(e) => return Err(io::Error::other(e)),
-        };
+    fn new(color: bool) -> io::Result<Self> {
+        let rl = DefaultEditor::new().map_err(readline_error)?;
+        let termination = Arc::new(AtomicU8::new(TERMINATION_NONE));
+        let stray_interrupt = Arc::new(AtomicBool::new(false));
+        #[cfg(unix)]
+        let signals =
+            watch_for_termination(Arc::clone(&termination), Arc::clone(&stray_interrupt))?;
         let stdout = io::stdout().lock();
         Ok(ReadlineInterface {
             rl,
             stdout,
+            color,
+            prompt: if color { "\x1B[1;32m> \x1B[0m" } else { "> " },
             wrote_prompt: false,
             wrote_last_output: true,
+            interrupted: false,
+            termination,
+            stray_interrupt,
+            #[cfg(unix)]
+            signals,
         })
     }
+
+    // Check for signals received while not reading input
+    fn check_signals(&mut self) -> io::Result<()> {
+        if self.stray_interrupt.swap(false, Ordering::SeqCst) {
+            self.interrupted = true;
+        }
+        self.check_terminated()
+    }
+
+    fn check_terminated(&self) -> io::Result<()> {
+        if self.termination.load(Ordering::SeqCst) != TERMINATION_NONE {
+            self.termination
+                .store(TERMINATION_NOTICED, Ordering::SeqCst);
+            Err(Terminated.into())
+        } else {
+            Ok(())
+        }
+    }
+
+    // Ask the player whether they want to quit after they press Ctrl-C twice
+    // in a row.  End of input or another Ctrl-C counts as a yes.
+    fn confirm_quit(&mut self) -> io::Result<bool> {
+        match self.rl.readline("Really quit? (y/n) ") {
+            Ok(answer) => Ok(answer.trim_start().starts_with(['y', 'Y'])),
+            Err(ReadlineError::Eof | ReadlineError::Interrupted) => {
+                self.check_terminated()?;
+                Ok(true)
+            }
+            Err(e) => Err(readline_error(e)),
+        }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for ReadlineInterface {
+    fn drop(&mut self) {
+        self.signals.close();
+    }
 }
 
 impl Interface for ReadlineInterface {
@@ -148,22 +322,269 @@ impl Interface for ReadlineInterface {
         Ok(())
     }
 
+    /// If color is enabled, parser errors are shown in yellow, system
+    /// messages in cyan, and status messages in bold.
+    fn show_messages(&mut self, messages: &[Message], _ctx: &OutputContext) -> io::Result<()> {
+        if !self.color {
+            return self.show_output(&join_messages(messages));
+        }
+        let text = messages
+            .iter()
+            .map(|m| match m.kind {
+                MessageKind::Narrative => m.text.clone(),
+                MessageKind::ParserError => format!("\x1B[33m{}\x1B[0m", m.text),
+                MessageKind::System => format!("\x1B[36m{}\x1B[0m", m.text),
+                MessageKind::Status => format!("\x1B[1m{}\x1B[0m", m.text),
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        self.show_output(&text)
+    }
+
     fn get_input(&mut self) -> io::Result<Option<String>> {
         if self.wrote_last_output {
             writeln!(&mut self.stdout)?;
         }
         self.wrote_prompt = true;
         loop {
-            match self.rl.readline("> ") {
-                Ok(line) => return Ok(Some(line)),
-                Err(ReadlineError::Io(e)) => return Err(e),
+            self.check_signals()?;
+            match self.rl.readline(self.prompt) {
+                Ok(line) => {
+                    self.interrupted = false;
+                    self.rl
+                        .add_history_entry(line.as_str())
+                        .map_err(readline_error)?;
+                    return Ok(Some(line));
+                }
                 Err(ReadlineError::Eof) => return Ok(None),
-                Err(ReadlineError::Interrupted) => return Ok(None),
-                #[cfg(unix)]
-                Err(ReadlineError::Errno(e)) => return Err(e.into()),
-                Err(ReadlineError::Signal(_)) => (), // TODO: Rethink
-                Err(e) => return Err(io::Error::other(e)),
+                Err(ReadlineError::Interrupted) => {
+                    self.check_terminated()?;
+                    if std::mem::replace(&mut self.interrupted, true) {
+                        if self.confirm_quit()? {
+                            return Ok(None);
+                        }
+                        self.interrupted = false;
+                    }
+                }
+                // rustyline redraws the line itself when the terminal is
+                // resized, and `columns()` always reports the current width.
+                Err(ReadlineError::Signal(_)) => (),
+                Err(e) => return Err(readline_error(e)),
+            }
+        }
+    }
+
+    fn columns(&mut self) -> Option<usize> {
+        self.rl.dimensions().map(|(cols, _)| usize::from(cols))
+    }
+}
+
+fn readline_error(e: ReadlineError) -> io::Error {
+    match e {
+        ReadlineError::Io(e) => e,
+        #[cfg(unix)]
+        ReadlineError::Errno(e) => e.into(),
+        e => io::Error::other(e),
+    }
+}
+
+// No terminating signal has been received
+const TERMINATION_NONE: u8 = 0;
+// A terminating signal has been received, but `get_input()` has not noticed
+#[cfg(unix)]
+const TERMINATION_PENDING: u8 = 1;
+// `get_input()` has noticed a terminating signal
+const TERMINATION_NOTICED: u8 = 2;
+
+/// How long to wait for `get_input()` to notice a terminating signal before
+/// letting the signal terminate the process without saving the game
+#[cfg(unix)]
+const TERMINATION_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// How often to interrupt `readline()` while waiting for `get_input()` to
+/// notice a terminating signal
+#[cfg(unix)]
+const TERMINATION_RETRY_INTERVAL: std::time::Duration = std::time::Duration::from_millis(50);
+
+// Start a thread that updates `termination` when the process receives SIGTERM
+// or SIGHUP and then wakes up any pending call to `readline()` on the current
+// thread, and that sets `stray_interrupt` when the process receives SIGINT
+// outside of `readline()`
+#[cfg(unix)]
+fn watch_for_termination(
+    termination: Arc<AtomicU8>,
+    stray_interrupt: Arc<AtomicBool>,
+) -> io::Result<signal_hook::iterator::Handle> {
+    use nix::sys::pthread::{pthread_kill, pthread_self};
+    use nix::sys::signal::Signal;
+    use signal_hook::consts::{SIGHUP, SIGINT, SIGTERM};
+    let reader = pthread_self();
+    // SIGINT is caught too so that the ones sent below are harmless if they
+    // arrive outside of `readline()` (which installs its own handler).  Those
+    // are only sent once a termination is pending, so any SIGINT received
+    // before then came from elsewhere (e.g., Ctrl-C while the game was busy)
+    // and is passed on to `get_input()` as a press of Ctrl-C.
+    let mut signals = signal_hook::iterator::Signals::new([SIGTERM, SIGHUP, SIGINT])?;
+    let handle = signals.handle();
+    std::thread::spawn(move || {
+        for sig in signals.forever() {
+            if sig == SIGINT {
+                if termination.load(Ordering::SeqCst) == TERMINATION_NONE {
+                    stray_interrupt.store(true, Ordering::SeqCst);
+                }
+                continue;
+            }
+            if termination
+                .compare_exchange(
+                    TERMINATION_NONE,
+                    TERMINATION_PENDING,
+                    Ordering::SeqCst,
+                    Ordering::SeqCst,
+                )
+                .is_err()
+            {
+                continue;
             }
+            // rustyline's SIGINT handler interrupts the read that it's blocked
+            // in, after which it returns `Interrupted` (see the note on the
+            // rustyline dependency in Cargo.toml).  A SIGINT that arrives
+            // while the reading thread is still handling the terminating
+            // signal can fail to interrupt the read, though, so keep sending
+            // them until `get_input()` notices.  If it hasn't within
+            // `TERMINATION_TIMEOUT` (because the game is stuck, or a future
+            // rustyline doesn't let SIGINT interrupt the read), terminate the
+            // process the way the signal would have if it weren't caught.
+            let deadline = std::time::Instant::now() + TERMINATION_TIMEOUT;
+            while termination.load(Ordering::SeqCst) == TERMINATION_PENDING {
+                if std::time::Instant::now() >= deadline {
+                    let _ = signal_hook::low_level::emulate_default_handler(sig);
+                    break;
+                }
+                let _ = pthread_kill(reader, Signal::SIGINT);
+                std::thread::sleep(TERMINATION_RETRY_INTERVAL);
+            }
+        }
+    });
+    Ok(handle)
+}
+
+#[cfg(test)]
+#[cfg(unix)]
+mod tests {
+    use super::*;
+    use nix::pty::openpty;
+    use nix::sys::signal::{Signal, kill};
+    use nix::unistd::Pid;
+    use std::io::Read;
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::{Child, Command, ExitStatus, Stdio};
+    use std::time::{Duration, Instant};
+
+    // Set in the environment of the child processes run by the SIGTERM tests
+    const CHILD_VAR: &str = "ADVCORE_TEST_SIGTERM_CHILD";
+
+    // Rerun the test `name` in a child process
+    fn spawn_child(name: &str, stdin: Stdio, stdout: Stdio) -> Child {
+        Command::new(std::env::current_exe().unwrap())
+            .args(["--exact", name, "--nocapture"])
+            .env(CHILD_VAR, "1")
+            .stdin(stdin)
+            .stdout(stdout)
+            .spawn()
+            .unwrap()
+    }
+
+    // Send SIGTERM to `child` and wait for it to exit, failing rather than
+    // hanging if it doesn't
+    fn terminate_child(mut child: Child) -> ExitStatus {
+        let pid = Pid::from_raw(i32::try_from(child.id()).unwrap());
+        kill(pid, Signal::SIGTERM).unwrap();
+        let deadline = Instant::now() + TERMINATION_TIMEOUT + Duration::from_secs(10);
+        loop {
+            if let Some(status) = child.try_wait().unwrap() {
+                return status;
+            }
+            if Instant::now() > deadline {
+                let _ = child.kill();
+                panic!("child process was not terminated by SIGTERM");
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        }
+    }
+
+    // `readline()` only installs its SIGINT handler when reading from a
+    // terminal, so this test reruns itself in a child process attached to a
+    // pseudoterminal and sends that SIGTERM once it's waiting for input.
+    #[test]
+    fn test_sigterm_during_input() {
+        if std::env::var_os(CHILD_VAR).is_some() {
+            let mut iface = ReadlineInterface::new(false).unwrap();
+            let r = iface.get_input();
+            assert!(matches!(&r, Err(e) if Terminated::is(e)), "{r:?}");
+            return;
+        }
+        let pty = openpty(None, None).unwrap();
+        let child = spawn_child(
+            "interface::tests::test_sigterm_during_input",
+            Stdio::from(pty.slave.try_clone().unwrap()),
+            Stdio::from(pty.slave),
+        );
+        let mut master = std::fs::File::from(pty.master);
+        let mut output = Vec::new();
+        let mut byte = [0u8; 1];
+        while !output.ends_with(b"> ") {
+            assert_eq!(master.read(&mut byte).unwrap(), 1);
+            output.push(byte[0]);
+        }
+        let start = Instant::now();
+        let status = terminate_child(child);
+        // Being killed by the signal means that `get_input()` didn't notice
+        // it within `TERMINATION_TIMEOUT`.
+        assert!(status.success(), "{status:?}");
+        assert!(start.elapsed() < TERMINATION_TIMEOUT);
+    }
+
+    // If `get_input()` isn't called after SIGTERM, the signal terminates the
+    // process once `TERMINATION_TIMEOUT` has passed.
+    #[test]
+    fn test_sigterm_while_busy() {
+        if std::env::var_os(CHILD_VAR).is_some() {
+            let _iface = ReadlineInterface::new(false).unwrap();
+            println!("ready");
+            std::thread::sleep(Duration::from_secs(60));
+            panic!("not terminated by SIGTERM");
+        }
+        let mut child = spawn_child(
+            "interface::tests::test_sigterm_while_busy",
+            Stdio::null(),
+            Stdio::piped(),
+        );
+        let mut stdout = child.stdout.take().unwrap();
+        let mut output = Vec::new();
+        let mut byte = [0u8; 1];
+        while !output.ends_with(b"ready\n") {
+            assert_eq!(stdout.read(&mut byte).unwrap(), 1);
+            output.push(byte[0]);
+        }
+        let start = Instant::now();
+        let status = terminate_child(child);
+        assert_eq!(status.signal(), Some(signal_hook::consts::SIGTERM));
+        assert!(start.elapsed() >= TERMINATION_TIMEOUT);
+    }
+
+    #[test]
+    fn test_sigint_outside_readline() {
+        let mut iface = ReadlineInterface::new(false).unwrap();
+        nix::sys::signal::raise(Signal::SIGINT).unwrap();
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while !iface.stray_interrupt.load(Ordering::SeqCst) {
+            assert!(Instant::now() < deadline, "SIGINT was not noticed");
+            std::thread::sleep(Duration::from_millis(10));
         }
+        // The SIGINT counts as a first press of Ctrl-C, not as a request to
+        // terminate.
+        iface.check_signals().unwrap();
+        assert!(iface.interrupted);
+        assert_eq!(iface.termination.load(Ordering::SeqCst), TERMINATION_NONE);
     }
 }
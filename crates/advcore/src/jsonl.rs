@@ -0,0 +1,266 @@
+//! An interface that communicates in [JSON Lines](https://jsonlines.org) for
+//! use by other programs
+
+use crate::interface::{Interface, InterfaceBuilder, OutputContext};
+use crate::{Message, join_messages};
+use serde_json::{Value, json};
+use std::io::{self, BufRead, Write};
+
+/// The version of the JSON Lines schema implemented by [`JsonLinesInterface`]
+pub const JSON_LINES_VERSION: u64 = 1;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct JsonLinesInterfaceBuilder<R, W> {
+    reader: R,
+    writer: W,
+}
+
+impl<R, W> JsonLinesInterfaceBuilder<R, W> {
+    pub fn new(reader: R, writer: W) -> Self {
+        JsonLinesInterfaceBuilder { reader, writer }
+    }
+}
+
+impl<R: BufRead, W: Write> InterfaceBuilder for JsonLinesInterfaceBuilder<R, W> {
+    type Interface = JsonLinesInterface<R, W>;
+
+    fn build_interface(self) -> io::Result<Self::Interface> {
+        Ok(JsonLinesInterface {
+            reader: self.reader,
+            writer: self.writer,
+        })
+    }
+}
+
+/// An [`Interface`] that reads commands and writes output as JSON objects,
+/// one per line
+///
+/// # Schema (version 1)
+///
+/// Every line written by the interface is a JSON object with a `"version"`
+/// field (currently always `1`) and a `"type"` field indicating the kind of
+/// event:
+///
+/// - `"output"` — Output from the game.  Fields:
+///     - `"text"` (string) — the text of the output; may be empty
+///     - `"messages"` (array) — the output broken up into messages, each of
+///       which is an object with the fields:
+///         - `"kind"` (string) — one of `"narrative"`, `"parser_error"`,
+///           `"system"`, or `"status"`; see [`MessageKind`](crate::MessageKind)
+///         - `"text"` (string) — the text of the message
+///
+///       `"text"` is the text of the messages joined together with newlines.
+///     - `"ended"` (boolean) — whether the game has ended, in which case no
+///       more input will be read
+///     - `"location"` (string or null) — the name of the player's current
+///       location, if known
+///     - `"status"` (string or null) — a summary of the player's progress,
+///       such as their score, if the game provides one
+///     - `"turn"` (integer) — the number of commands the game has handled so
+///       far
+///
+/// - `"error"` — A line of input could not be understood and was ignored.
+///   Fields:
+///     - `"message"` (string) — a description of the problem
+///
+/// Every line of input must be a JSON object with a `"command"` field whose
+/// value is a string containing the command to give to the game.  The object
+/// may also contain a `"version"` field, which must equal `1` if present.
+/// Other fields are ignored, as are blank lines.
+///
+/// New fields may be added to objects in the future without changing the
+/// version number; clients should ignore fields they do not recognize.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct JsonLinesInterface<R, W> {
+    reader: R,
+    writer: W,
+}
+
+impl<R: BufRead, W: Write> JsonLinesInterface<R, W> {
+    fn emit(&mut self, event: &Value) -> io::Result<()> {
+        writeln!(&mut self.writer, "{event}")?;
+        self.writer.flush()
+    }
+}
+
+impl<R: BufRead, W: Write> Interface for JsonLinesInterface<R, W> {
+    /// Write an output event without any details about the game's state; the
+    /// `"location"` field will be null and `"turn"` will be 0.
+    fn show_output(&mut self, text: &str) -> io::Result<()> {
+        self.show_output_with_context(text, &OutputContext::default())
+    }
+
+    fn show_output_with_context(&mut self, text: &str, ctx: &OutputContext) -> io::Result<()> {
+        self.show_messages(&text_messages(text), ctx)
+    }
+
+    fn show_messages(&mut self, messages: &[Message], ctx: &OutputContext) -> io::Result<()> {
+        self.emit(&output_event(messages, ctx))
+    }
+
+    fn get_input(&mut self) -> io::Result<Option<String>> {
+        loop {
+            let mut line = String::new();
+            if self.reader.read_line(&mut line)? == 0 {
+                return Ok(None);
+            }
+            if line.trim().is_empty() {
+                continue;
+            }
+            match parse_command(&line) {
+                Ok(command) => return Ok(Some(command)),
+                Err(message) => self.emit(&error_event(&message))?,
+            }
+        }
+    }
+}
+
+// The messages to report for output given only as text
+pub(crate) fn text_messages(text: &str) -> Vec<Message> {
+    if text.is_empty() {
+        Vec::new()
+    } else {
+        vec![Message::narrative(text)]
+    }
+}
+
+pub(crate) fn output_event(messages: &[Message], ctx: &OutputContext) -> Value {
+    let messages_json = messages
+        .iter()
+        .map(|m| json!({"kind": m.kind.as_str(), "text": m.text}))
+        .collect::<Vec<_>>();
+    json!({
+        "version": JSON_LINES_VERSION,
+        "type": "output",
+        "text": join_messages(messages),
+        "messages": messages_json,
+        "ended": ctx.ended,
+        "location": ctx.location,
+        "status": ctx.status,
+        "turn": ctx.turn,
+    })
+}
+
+pub(crate) fn error_event(message: &str) -> Value {
+    json!({
+        "version": JSON_LINES_VERSION,
+        "type": "error",
+        "message": message,
+    })
+}
+
+// Extract the command from a line of input, or return an error message
+pub(crate) fn parse_command(line: &str) -> Result<String, String> {
+    let value = serde_json::from_str::<Value>(line).map_err(|e| format!("invalid JSON: {e}"))?;
+    let Value::Object(obj) = value else {
+        return Err(String::from("input must be a JSON object"));
+    };
+    if let Some(v) = obj.get("version") {
+        if v.as_u64() != Some(JSON_LINES_VERSION) {
+            return Err(format!("unsupported version: {v}"));
+        }
+    }
+    match obj.get("command") {
+        Some(Value::String(s)) => Ok(s.clone()),
+        Some(_) => Err(String::from("\"command\" must be a string")),
+        None => Err(String::from("missing \"command\" field")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session() {
+        let input = concat!(
+            "{\"command\": \"look\"}\n",
+            "\n",
+            "not json\n",
+            "[\"look\"]\n",
+            "{\"command\": 42}\n",
+            "{\"version\": 2, \"command\": \"look\"}\n",
+            "{\"version\": 1, \"command\": \"quit\", \"extra\": true}\n",
+        );
+        let mut iface = JsonLinesInterfaceBuilder::new(input.as_bytes(), Vec::new())
+            .build_interface()
+            .unwrap();
+        iface
+            .show_output_with_context(
+                "Hello.",
+                &OutputContext {
+                    ended: false,
+                    location: Some(String::from("Hall")),
+                    status: Some(String::from("Score: 0")),
+                    turn: 0,
+                },
+            )
+            .unwrap();
+        assert_eq!(iface.get_input().unwrap().as_deref(), Some("look"));
+        assert_eq!(iface.get_input().unwrap().as_deref(), Some("quit"));
+        iface
+            .show_messages(
+                &[Message::parser_error("Huh?"), Message::narrative("Bye.")],
+                &OutputContext {
+                    ended: true,
+                    location: None,
+                    status: None,
+                    turn: 2,
+                },
+            )
+            .unwrap();
+        assert_eq!(iface.get_input().unwrap(), None);
+        let lines = String::from_utf8(iface.writer)
+            .unwrap()
+            .lines()
+            .map(|ln| serde_json::from_str::<Value>(ln).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(lines.len(), 6);
+        assert_eq!(
+            lines[0],
+            json!({
+                "version": 1,
+                "type": "output",
+                "text": "Hello.",
+                "messages": [{"kind": "narrative", "text": "Hello."}],
+                "ended": false,
+                "location": "Hall",
+                "status": "Score: 0",
+                "turn": 0,
+            })
+        );
+        assert_eq!(lines[1]["type"], "error");
+        assert!(
+            lines[1]["message"]
+                .as_str()
+                .unwrap()
+                .starts_with("invalid JSON:")
+        );
+        assert_eq!(
+            lines[2],
+            json!({
+                "version": 1,
+                "type": "error",
+                "message": "input must be a JSON object",
+            })
+        );
+        assert_eq!(lines[3]["message"], "\"command\" must be a string");
+        assert_eq!(lines[4]["message"], "unsupported version: 2");
+        assert_eq!(
+            lines[5],
+            json!({
+                "version": 1,
+                "type": "output",
+                "text": "Huh?\nBye.",
+                "messages": [
+                    {"kind": "parser_error", "text": "Huh?"},
+                    {"kind": "narrative", "text": "Bye."},
+                ],
+                "ended": true,
+                "location": null,
+                "status": null,
+                "turn": 2,
+            })
+        );
+    }
+}
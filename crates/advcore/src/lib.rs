@@ -1,5 +1,33 @@
+mod abbreviations;
+mod async_interface;
+mod graph;
 mod interface;
+mod jsonl;
+mod map;
+mod server;
+mod sessions;
+mod spelling;
+mod telnet;
+#[cfg(test)]
+mod testutil;
+#[cfg(feature = "tui")]
+mod tui;
+#[cfg(feature = "web")]
+mod web;
+mod wrap;
+pub use crate::abbreviations::*;
+pub use crate::async_interface::*;
+pub use crate::graph::*;
 pub use crate::interface::*;
+pub use crate::jsonl::*;
+pub use crate::map::*;
+pub use crate::server::*;
+pub use crate::sessions::*;
+pub use crate::spelling::*;
+pub use crate::telnet::*;
+#[cfg(feature = "tui")]
+pub use crate::tui::*;
+pub use crate::wrap::*;
 use std::io::{self, ErrorKind};
 use std::process::ExitCode;
 
@@ -11,45 +39,452 @@ pub trait GameBuilder: Sized {
 
 pub trait GameEngine: Sized {
     fn handle_input(self, input: &str) -> Output<Self>;
+
+    /// Handles the player's answer to a yes-or-no question asked by returning
+    /// [`Output::Confirm`]: `true` for yes, `false` for no.  The default
+    /// implementation continues the game without any output.
+    fn handle_answer(self, answer: bool) -> Output<Self> {
+        let _ = answer;
+        Output::Continue {
+            game: self,
+            messages: Vec::new(),
+        }
+    }
+
+    /// Returns the name of the player's current location, for interfaces that
+    /// display it separately from the game's text.  The default
+    /// implementation returns `None`.
+    fn location(&self) -> Option<String> {
+        None
+    }
+
+    /// Returns a short summary of the player's progress, such as their
+    /// score, for interfaces that display a status line.  The default
+    /// implementation returns `None`.
+    fn status(&self) -> Option<String> {
+        None
+    }
+
+    /// Saves the game at the player's request, after the game returns
+    /// [`Output::Save`].  The game may update itself to record where it was
+    /// saved; this is only called if the program running the game allows
+    /// saving.  The default implementation fails with
+    /// [`ErrorKind::Unsupported`].
+    fn save_game(&mut self) -> io::Result<()> {
+        Err(io::Error::new(
+            ErrorKind::Unsupported,
+            "this game cannot be saved",
+        ))
+    }
+
+    /// Saves the game somewhere that the player can resume it from.  This is
+    /// called when the program is asked to terminate while waiting for input
+    /// (see [`Terminated`]).  The default implementation does nothing.
+    fn autosave(&self) -> io::Result<()> {
+        Ok(())
+    }
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum Output<G> {
-    // `text` should not end with a newline.
-    Continue { game: G, text: String },
-    Goodbye { text: String },
+    Continue {
+        game: G,
+        messages: Vec<Message>,
+    },
+    /// Continue the game after asking the player a yes-or-no question.  The
+    /// player's next input is taken as the answer and passed to
+    /// [`GameEngine::handle_answer()`] instead of
+    /// [`GameEngine::handle_input()`]; inputs other than "yes" or "no" ask
+    /// the question again.
+    Confirm {
+        game: G,
+        messages: Vec<Message>,
+    },
+    /// Continue the game after saving it.  The program running the game
+    /// saves it by calling [`GameEngine::save_game()`], unless it doesn't
+    /// allow saving (as when hosting games for remote players), and tells the
+    /// player how that went after showing `messages`.
+    Save {
+        game: G,
+        messages: Vec<Message>,
+    },
+    Goodbye {
+        messages: Vec<Message>,
+    },
 }
 
 impl<G> Output<G> {
-    pub fn text(&self) -> &str {
+    /// Continue the game, showing `text` as a single narrative message (or
+    /// nothing, if `text` is empty)
+    pub fn continue_with<S: Into<String>>(game: G, text: S) -> Self {
+        Output::Continue {
+            game,
+            messages: Message::narrative(text).into_vec(),
+        }
+    }
+
+    /// Ask the player the yes-or-no question `question`, shown as a single
+    /// narrative message
+    pub fn confirm<S: Into<String>>(game: G, question: S) -> Self {
+        Output::Confirm {
+            game,
+            messages: Message::narrative(question).into_vec(),
+        }
+    }
+
+    /// End the game, showing `text` as a single narrative message (or
+    /// nothing, if `text` is empty)
+    pub fn goodbye<S: Into<String>>(text: S) -> Self {
+        Output::Goodbye {
+            messages: Message::narrative(text).into_vec(),
+        }
+    }
+
+    pub fn messages(&self) -> &[Message] {
         match self {
-            Output::Continue { text, .. } => text,
-            Output::Goodbye { text } => text,
+            Output::Continue { messages, .. }
+            | Output::Confirm { messages, .. }
+            | Output::Save { messages, .. } => messages,
+            Output::Goodbye { messages } => messages,
         }
     }
 
+    /// Returns the text of all of the messages joined together with newlines
+    pub fn text(&self) -> String {
+        join_messages(self.messages())
+    }
+
     pub fn into_game(self) -> Option<G> {
-        if let Output::Continue { game, .. } = self {
-            Some(game)
+        self.into_pending().map(|(game, _)| game)
+    }
+
+    // Returns the game, if it hasn't ended, along with whether it's waiting
+    // for the answer to a question
+    fn into_pending(self) -> Option<(G, bool)> {
+        match self {
+            Output::Continue { game, .. } | Output::Save { game, .. } => Some((game, false)),
+            Output::Confirm { game, .. } => Some((game, true)),
+            Output::Goodbye { .. } => None,
+        }
+    }
+}
+
+/// A piece of a game's output
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Message {
+    pub kind: MessageKind,
+    /// The text of the message, which should not end with a newline
+    pub text: String,
+}
+
+impl Message {
+    pub fn new<S: Into<String>>(kind: MessageKind, text: S) -> Message {
+        Message {
+            kind,
+            text: text.into(),
+        }
+    }
+
+    pub fn narrative<S: Into<String>>(text: S) -> Message {
+        Message::new(MessageKind::Narrative, text)
+    }
+
+    pub fn parser_error<S: Into<String>>(text: S) -> Message {
+        Message::new(MessageKind::ParserError, text)
+    }
+
+    pub fn system<S: Into<String>>(text: S) -> Message {
+        Message::new(MessageKind::System, text)
+    }
+
+    pub fn status<S: Into<String>>(text: S) -> Message {
+        Message::new(MessageKind::Status, text)
+    }
+
+    // Returns the message in a `Vec`, or an empty `Vec` if it has no text
+    fn into_vec(self) -> Vec<Message> {
+        if self.text.is_empty() {
+            Vec::new()
         } else {
-            None
+            vec![self]
         }
     }
 }
 
-pub fn run_game<I: InterfaceBuilder, G: GameBuilder>(ifsrc: I, game: G) -> io::Result<()> {
-    let mut r = game.start();
+/// The kinds of [`Message`], which interfaces may display differently
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum MessageKind {
+    /// The story: descriptions of the world and of the results of the
+    /// player's actions
+    Narrative,
+    /// An explanation of why the player's input could not be understood
+    ParserError,
+    /// A message about the game itself rather than the story, such as "Game
+    /// saved."
+    System,
+    /// A change in the player's status, such as their score
+    Status,
+}
+
+impl MessageKind {
+    /// Returns the name of the kind in `snake_case`, as used by
+    /// [`JsonLinesInterface`]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MessageKind::Narrative => "narrative",
+            MessageKind::ParserError => "parser_error",
+            MessageKind::System => "system",
+            MessageKind::Status => "status",
+        }
+    }
+}
+
+/// Join the text of `messages` together with newlines
+pub fn join_messages(messages: &[Message]) -> String {
+    messages
+        .iter()
+        .map(|m| m.text.as_str())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+impl<G: GameEngine> Output<G> {
+    // The details to show alongside this output, given the number of inputs
+    // handled so far
+    fn context(&self, turn: u64) -> OutputContext {
+        match self {
+            Output::Continue { game, .. }
+            | Output::Confirm { game, .. }
+            | Output::Save { game, .. } => OutputContext {
+                ended: false,
+                location: game.location(),
+                status: game.status(),
+                turn,
+            },
+            Output::Goodbye { .. } => OutputContext {
+                ended: true,
+                location: None,
+                status: None,
+                turn,
+            },
+        }
+    }
+
+    // If this output asks for the game to be saved, save it (if `allowed`)
+    // and add a message saying how that went
+    fn save_if_requested(self, allowed: bool) -> Self {
+        let Output::Save {
+            mut game,
+            mut messages,
+        } = self
+        else {
+            return self;
+        };
+        messages.push(Message::system(if !allowed {
+            String::from("Saving is not available here.")
+        } else {
+            match game.save_game() {
+                Ok(()) => String::from("Game saved."),
+                Err(e) => format!("The game could not be saved: {e}"),
+            }
+        }));
+        Output::Continue { game, messages }
+    }
+}
+
+/// Run a game on an interface until the game ends or the input runs out
+///
+/// In addition to the game's own commands, the player can enter `RESTART` to
+/// abandon the current game (after confirming) and start a new one from a
+/// clone of `game`.
+pub fn run_game<I: InterfaceBuilder, G: GameBuilder + Clone>(ifsrc: I, game: G) -> io::Result<()> {
+    play_game(ifsrc, game, true)
+}
+
+// Like `run_game()`, but the game is only allowed to save itself if `saving`
+// is true
+pub(crate) fn play_game<I: InterfaceBuilder, G: GameBuilder + Clone>(
+    ifsrc: I,
+    game: G,
+    saving: bool,
+) -> io::Result<()> {
+    let mut play = Play::start(game, saving);
     let mut iface = ifsrc.build_interface()?;
     loop {
-        iface.show_output(r.text())?;
-        let Some(game) = r.into_game() else {
+        iface.show_messages(play.output.messages(), &play.context())?;
+        if play.ended() {
             return Ok(());
-        };
-        let Some(input) = iface.get_input()? else {
+        }
+        let input = match iface.get_input() {
+            Ok(Some(input)) => input,
             // End of input
+            Ok(None) => return Ok(()),
+            Err(e) => return Err(play.terminate(e)),
+        };
+        play = play.handle_input(&input);
+    }
+}
+
+/// The asynchronous counterpart of [`run_game()`], for running many games on
+/// one async runtime
+pub async fn run_game_async<I, G>(ifsrc: I, game: G) -> io::Result<()>
+where
+    I: AsyncInterfaceBuilder + Send,
+    I::Interface: Send,
+    G: GameBuilder + Clone + Send,
+    G::Engine: Send,
+{
+    let mut play = Play::start(game, true);
+    let mut iface = ifsrc.build_interface().await?;
+    loop {
+        iface
+            .show_messages(play.output.messages(), &play.context())
+            .await?;
+        if play.ended() {
             return Ok(());
+        }
+        let input = match iface.get_input().await {
+            Ok(Some(input)) => input,
+            // End of input
+            Ok(None) => return Ok(()),
+            Err(e) => return Err(play.terminate(e)),
+        };
+        play = play.handle_input(&input);
+    }
+}
+
+// The state of a game being run by `run_game()` or `run_game_async()`, which
+// handle the RESTART command themselves
+#[derive(Debug)]
+struct Play<G: GameBuilder> {
+    builder: G,
+    // The game's most recent output
+    output: Output<G::Engine>,
+    // The number of inputs handled since the game started
+    turn: u64,
+    // Whether `output` asks the player whether to restart
+    restarting: bool,
+    // Whether the game may be saved
+    saving: bool,
+}
+
+impl<G: GameBuilder + Clone> Play<G> {
+    fn start(builder: G, saving: bool) -> Self {
+        let output = builder.clone().start().save_if_requested(saving);
+        Play {
+            builder,
+            output,
+            turn: 0,
+            restarting: false,
+            saving,
+        }
+    }
+
+    fn context(&self) -> OutputContext {
+        self.output.context(self.turn)
+    }
+
+    fn ended(&self) -> bool {
+        matches!(self.output, Output::Goodbye { .. })
+    }
+
+    // Handle an error from `Interface::get_input()`, autosaving the game if
+    // the program is being terminated
+    fn terminate(&self, e: io::Error) -> io::Error {
+        match &self.output {
+            Output::Continue { game, .. }
+            | Output::Confirm { game, .. }
+            | Output::Save { game, .. }
+                if Terminated::is(&e) =>
+            {
+                game.autosave().err().unwrap_or(e)
+            }
+            _ => e,
+        }
+    }
+
+    fn handle_input(self, input: &str) -> Self {
+        let Play {
+            builder,
+            output,
+            turn,
+            restarting,
+            saving,
+        } = self;
+        let Some((game, confirming)) = output.into_pending() else {
+            // The game has already ended.
+            return Play {
+                builder,
+                output: Output::Goodbye {
+                    messages: Vec::new(),
+                },
+                turn,
+                restarting: false,
+                saving,
+            };
+        };
+        let (output, restarting) = if restarting {
+            match parse_answer(input) {
+                Some(true) => return Play::start(builder, saving),
+                Some(false) => (
+                    Output::Continue {
+                        game,
+                        messages: vec![Message::system("Okay.")],
+                    },
+                    false,
+                ),
+                None => (ask_again(game), true),
+            }
+        } else if !confirming && input.trim().eq_ignore_ascii_case("restart") {
+            (
+                Output::Confirm {
+                    game,
+                    messages: vec![Message::system("Are you sure you want to restart?")],
+                },
+                true,
+            )
+        } else {
+            (
+                respond(game, confirming, input).save_if_requested(saving),
+                false,
+            )
         };
-        r = game.handle_input(&input);
+        Play {
+            builder,
+            output,
+            turn: turn + 1,
+            restarting,
+            saving,
+        }
+    }
+}
+
+// Pass a line of input to `game`, taking it as the answer to a question if
+// `confirming` is true
+fn respond<G: GameEngine>(game: G, confirming: bool, input: &str) -> Output<G> {
+    if !confirming {
+        return game.handle_input(input);
+    }
+    match parse_answer(input) {
+        Some(answer) => game.handle_answer(answer),
+        None => ask_again(game),
+    }
+}
+
+// Parse an answer to a yes-or-no question
+fn parse_answer(input: &str) -> Option<bool> {
+    match input.trim().to_ascii_lowercase().as_str() {
+        "y" | "yes" => Some(true),
+        "n" | "no" => Some(false),
+        _ => None,
+    }
+}
+
+// The response to an answer that isn't yes or no
+fn ask_again<G>(game: G) -> Output<G> {
+    Output::Confirm {
+        game,
+        messages: vec![Message::system("Please answer yes or no.")],
     }
 }
 
@@ -64,32 +499,62 @@ pub fn io_exit(r: io::Result<()>) -> ExitCode {
     }
 }
 
+/// Join a list of items into an English phrase, e.g., "A, B, or C"
+pub fn english_list<S: AsRef<str>>(items: &[S], conjunction: &str) -> String {
+    match items {
+        [] => String::new(),
+        [x] => x.as_ref().to_owned(),
+        [x, y] => format!("{} {conjunction} {}", x.as_ref(), y.as_ref()),
+        [init @ .., last] => {
+            let mut s = String::new();
+            for x in init {
+                s.push_str(x.as_ref());
+                s.push_str(", ");
+            }
+            s.push_str(conjunction);
+            s.push(' ');
+            s.push_str(last.as_ref());
+            s
+        }
+    }
+}
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Tester<G> {
     game: Option<G>,
-    last_output: Option<String>,
+    confirming: bool,
+    last_output: Option<Vec<Message>>,
 }
 
 impl<G: GameEngine> Tester<G> {
     pub fn start<B: GameBuilder<Engine = G>>(builder: B) -> Self {
-        builder.start().into()
+        builder.start().save_if_requested(true).into()
     }
 
     pub fn input(&mut self, input: &str) {
         let Some(game) = self.game.take() else {
             panic!("Tester::input() called after game finished");
         };
-        *self = game.handle_input(input).into();
+        *self = respond(game, self.confirming, input)
+            .save_if_requested(true)
+            .into();
     }
 
     pub fn assert_output<S: AsRef<str>>(&self, output: S) {
         if let Some(prev) = self.last_output.as_deref() {
-            assert_eq!(prev, output.as_ref());
+            assert_eq!(join_messages(prev), output.as_ref());
         } else {
             panic!("Tester::assert_output() called with no previous output");
         }
     }
 
+    pub fn last_messages(&self) -> &[Message] {
+        match self.last_output.as_deref() {
+            Some(messages) => messages,
+            None => panic!("Tester::last_messages() called with no previous output"),
+        }
+    }
+
     pub fn game(&self) -> &G {
         match self.game.as_ref() {
             Some(game) => game,
@@ -106,6 +571,7 @@ impl<G: GameEngine> From<G> for Tester<G> {
     fn from(game: G) -> Tester<G> {
         Tester {
             game: Some(game),
+            confirming: false,
             last_output: None,
         }
     }
@@ -113,10 +579,58 @@ impl<G: GameEngine> From<G> for Tester<G> {
 
 impl<G: GameEngine> From<Output<G>> for Tester<G> {
     fn from(output: Output<G>) -> Tester<G> {
-        let (game, last_output) = match output {
-            Output::Continue { game, text } => (Some(game), Some(text)),
-            Output::Goodbye { text } => (None, Some(text)),
+        let last_output = Some(output.messages().to_vec());
+        let (game, confirming) = match output.into_pending() {
+            Some((game, confirming)) => (Some(game), confirming),
+            None => (None, false),
         };
-        Tester { game, last_output }
+        Tester {
+            game,
+            confirming,
+            last_output,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::Counter;
+
+    #[test]
+    fn test_restart() {
+        let mut output = Vec::new();
+        let iface = BasicInterfaceBuilder::new(
+            &b"go\nrestart\nmaybe\nno\ngo\nRESTART\ny\ngo\n"[..],
+            &mut output,
+        );
+        run_game(iface, Counter(0)).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            concat!(
+                "Count!\n\n> \n",
+                "1\n\n> \n",
+                "Are you sure you want to restart?\n\n> \n",
+                "Please answer yes or no.\n\n> \n",
+                "Okay.\n\n> \n",
+                "2\n\n> \n",
+                "Are you sure you want to restart?\n\n> \n",
+                "Count!\n\n> \n",
+                "1\n\n> \n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_save_unsupported() {
+        let mut t = Tester::start(Counter(0));
+        t.input("save");
+        assert_eq!(
+            t.last_messages(),
+            [Message::system(
+                "The game could not be saved: this game cannot be saved"
+            )]
+        );
+        assert_eq!(t.game(), &Counter(0));
     }
 }
@@ -0,0 +1,265 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Horizontal space between adjacent rooms in a rendered map
+const COLUMN_GAP: usize = 3;
+
+/// Vertical space between adjacent rooms in a rendered map
+const ROW_GAP: usize = 1;
+
+/// Maximum number of grid columns or rows that will be drawn.  Maps spanning
+/// more than this are listed instead of drawn.
+const MAX_SPAN: usize = 64;
+
+/// An ASCII map of rooms laid out on a grid, for games whose rooms are
+/// connected along compass directions
+///
+/// Grid coordinates are `(x, y)` pairs, with `x` increasing eastwards and `y`
+/// increasing southwards.  Rooms without coordinates are listed below the map
+/// instead of being drawn, as are all rooms if the placed rooms span more than
+/// 64 columns or rows.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct GridMap {
+    placed: BTreeMap<(i32, i32), MapRoom>,
+    unplaced: Vec<MapRoom>,
+    links: BTreeSet<((i32, i32), (i32, i32))>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct MapRoom {
+    label: String,
+    here: bool,
+}
+
+impl GridMap {
+    pub fn new() -> Self {
+        GridMap::default()
+    }
+
+    /// Add a room to the map.  `label` should be short, as every room is
+    /// drawn as wide as the longest label.  `here` marks the room as the
+    /// player's current location.
+    ///
+    /// If `pos` is `None` or is already occupied, the room will be listed
+    /// below the map.
+    pub fn add_room(&mut self, pos: Option<(i32, i32)>, label: &str, here: bool) {
+        let room = MapRoom {
+            label: label.to_owned(),
+            here,
+        };
+        match pos {
+            Some(p) if !self.placed.contains_key(&p) => {
+                self.placed.insert(p, room);
+            }
+            _ => self.unplaced.push(room),
+        }
+    }
+
+    /// Draw a connection between the rooms at the given positions.  Only
+    /// connections between rooms that are next to each other horizontally,
+    /// vertically, or diagonally are drawn.
+    pub fn connect(&mut self, a: (i32, i32), b: (i32, i32)) {
+        let link = if a <= b { (a, b) } else { (b, a) };
+        self.links.insert(link);
+    }
+
+    pub fn render(&self) -> String {
+        let (mut lines, unplaced) = match self.render_grid() {
+            Some(lines) => (lines, self.unplaced.iter().collect::<Vec<_>>()),
+            None => (
+                Vec::new(),
+                self.placed.values().chain(&self.unplaced).collect(),
+            ),
+        };
+        if !unplaced.is_empty() {
+            if !lines.is_empty() {
+                lines.push(String::new());
+            }
+            let labels = unplaced
+                .into_iter()
+                .map(MapRoom::marked_label)
+                .collect::<Vec<_>>();
+            lines.push(format!("Not shown: {}", labels.join(", ")));
+        }
+        lines.join("\n")
+    }
+
+    // Returns `None` if the placed rooms span too many columns or rows to
+    // draw
+    fn render_grid(&self) -> Option<Vec<String>> {
+        let Some(((xmin, xmax), (ymin, ymax))) = self.bounds() else {
+            return Some(Vec::new());
+        };
+        let cols = span(xmin, xmax)?;
+        let rows = span(ymin, ymax)?;
+        let inner = self
+            .placed
+            .values()
+            .map(|r| r.marked_label().chars().count())
+            .max()
+            .unwrap_or(0)
+            + 2;
+        let box_width = inner + 2;
+        let width = cols * (box_width + COLUMN_GAP) - COLUMN_GAP;
+        let height = rows * (3 + ROW_GAP) - ROW_GAP;
+        let mut canvas = vec![vec![' '; width]; height];
+        let origin = |(x, y): (i32, i32)| {
+            let col = usize::try_from(x - xmin).unwrap_or(0);
+            let row = usize::try_from(y - ymin).unwrap_or(0);
+            (col * (box_width + COLUMN_GAP), row * (3 + ROW_GAP))
+        };
+        for (&pos, room) in &self.placed {
+            let (cx, cy) = origin(pos);
+            let border = format!("+{}+", "-".repeat(inner));
+            let middle = format!("|{:^inner$}|", room.marked_label());
+            for (dy, line) in [&border, &middle, &border].into_iter().enumerate() {
+                for (dx, ch) in line.chars().enumerate() {
+                    canvas[cy + dy][cx + dx] = ch;
+                }
+            }
+        }
+        for &(a, b) in &self.links {
+            if !(self.placed.contains_key(&a) && self.placed.contains_key(&b)) {
+                continue;
+            }
+            let (cx, cy) = origin(a);
+            let east = a.0.checked_add(1);
+            if Some(b) == east.map(|x| (x, a.1)) {
+                for ch in &mut canvas[cy + 1][cx + box_width..cx + box_width + COLUMN_GAP] {
+                    *ch = '-';
+                }
+            } else if Some(b) == a.1.checked_add(1).map(|y| (a.0, y)) {
+                for row in canvas.iter_mut().skip(cy + 3).take(ROW_GAP) {
+                    row[cx + box_width / 2] = '|';
+                }
+            } else if Some(b) == east.zip(a.1.checked_add(1)) {
+                let ch = &mut canvas[cy + 3][cx + box_width + COLUMN_GAP / 2];
+                *ch = if *ch == '/' { 'X' } else { '\\' };
+            } else if Some(b) == east.zip(a.1.checked_sub(1)) {
+                // `a` is the lower room of the pair, so the link goes in the
+                // gap above it.
+                let ch = &mut canvas[cy - ROW_GAP][cx + box_width + COLUMN_GAP / 2];
+                *ch = if *ch == '\\' { 'X' } else { '/' };
+            }
+        }
+        Some(
+            canvas
+                .into_iter()
+                .map(|row| row.into_iter().collect::<String>().trim_end().to_owned())
+                .collect(),
+        )
+    }
+
+    // Returns the minimum & maximum x and y coordinates of the placed rooms
+    fn bounds(&self) -> Option<((i32, i32), (i32, i32))> {
+        let xmin = self.placed.keys().map(|&(x, _)| x).min()?;
+        let xmax = self.placed.keys().map(|&(x, _)| x).max()?;
+        let ymin = self.placed.keys().map(|&(_, y)| y).min()?;
+        let ymax = self.placed.keys().map(|&(_, y)| y).max()?;
+        Some(((xmin, xmax), (ymin, ymax)))
+    }
+}
+
+// Returns the number of grid cells from `min` to `max` inclusive, or `None`
+// if that's more than `MAX_SPAN`
+fn span(min: i32, max: i32) -> Option<usize> {
+    let n = usize::try_from(i64::from(max) - i64::from(min) + 1).ok()?;
+    (n <= MAX_SPAN).then_some(n)
+}
+
+impl MapRoom {
+    fn marked_label(&self) -> String {
+        if self.here {
+            format!("*{}*", self.label)
+        } else {
+            self.label.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let mut map = GridMap::new();
+        map.add_room(Some((0, 0)), "A", false);
+        map.add_room(Some((1, 0)), "B", true);
+        map.add_room(Some((1, 1)), "CC", false);
+        map.add_room(Some((3, 1)), "D", false);
+        map.add_room(None, "E", false);
+        map.add_room(Some((0, 0)), "F", false);
+        map.connect((1, 0), (0, 0));
+        map.connect((1, 0), (1, 1));
+        map.connect((1, 1), (3, 1));
+        map.connect((0, 0), (1, 1));
+        assert_eq!(
+            map.render(),
+            concat!(
+                "+-----+   +-----+\n",
+                "|  A  |---| *B* |\n",
+                "+-----+   +-----+\n",
+                "        \\    |\n",
+                "          +-----+             +-----+\n",
+                "          | CC  |             |  D  |\n",
+                "          +-----+             +-----+\n",
+                "\n",
+                "Not shown: E, F",
+            )
+        );
+    }
+
+    #[test]
+    fn test_render_diagonals() {
+        let mut map = GridMap::new();
+        map.add_room(Some((0, 0)), "A", false);
+        map.add_room(Some((1, 0)), "B", false);
+        map.add_room(Some((0, 1)), "C", false);
+        map.add_room(Some((1, 1)), "D", false);
+        map.connect((0, 0), (1, 1));
+        map.connect((1, 0), (0, 1));
+        assert_eq!(
+            map.render(),
+            concat!(
+                "+---+   +---+\n",
+                "| A |   | B |\n",
+                "+---+   +---+\n",
+                "      X\n",
+                "+---+   +---+\n",
+                "| C |   | D |\n",
+                "+---+   +---+",
+            )
+        );
+    }
+
+    #[test]
+    fn test_render_too_wide() {
+        let mut map = GridMap::new();
+        map.add_room(Some((i32::MIN, 0)), "A", false);
+        map.add_room(Some((i32::MAX, 0)), "B", true);
+        map.add_room(None, "C", false);
+        map.connect((i32::MIN, 0), (i32::MAX, 0));
+        assert_eq!(map.render(), "Not shown: A, *B*, C");
+    }
+
+    #[test]
+    fn test_render_extreme_coordinates() {
+        let mut map = GridMap::new();
+        map.add_room(Some((i32::MAX, i32::MIN)), "A", false);
+        map.add_room(Some((i32::MAX, i32::MIN + 1)), "B", false);
+        map.connect((i32::MAX, i32::MIN), (i32::MAX, i32::MIN + 1));
+        assert_eq!(
+            map.render(),
+            concat!(
+                "+---+\n", "| A |\n", "+---+\n", "  |\n", "+---+\n", "| B |\n", "+---+",
+            )
+        );
+    }
+
+    #[test]
+    fn test_render_unplaced_only() {
+        let mut map = GridMap::new();
+        map.add_room(None, "Here", true);
+        assert_eq!(map.render(), "Not shown: *Here*");
+    }
+}
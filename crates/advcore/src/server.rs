@@ -0,0 +1,402 @@
+use crate::interface::{BasicInterfaceBuilder, Interface, InterfaceBuilder, OutputContext};
+use crate::telnet::TelnetInterfaceBuilder;
+use crate::wrap::WrapInterfaceBuilder;
+use crate::{GameBuilder, Message, play_game};
+use std::io::{self, BufReader, ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+/// A server that hosts an independent game session for each client that
+/// connects to a TCP or Unix socket
+///
+/// Each session runs in its own thread and, by default, talks to the client
+/// as plain lines of text, so clients like `netcat` can be used to play.
+/// Clients cannot save their games, as that would write to the host's files.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Server {
+    max_connections: usize,
+    idle_timeout: Option<Duration>,
+    wrap_width: usize,
+    telnet: bool,
+}
+
+impl Server {
+    pub fn new() -> Self {
+        Server {
+            max_connections: 16,
+            idle_timeout: Some(Duration::from_secs(600)),
+            wrap_width: 0,
+            telnet: false,
+        }
+    }
+
+    /// Set the maximum number of sessions that may be active at once.
+    /// Clients that connect while the server is full are told so and
+    /// disconnected.  Defaults to 16.
+    pub fn max_connections(mut self, max: usize) -> Self {
+        self.max_connections = max;
+        self
+    }
+
+    /// Set how long to wait for input from a client before ending its
+    /// session, or `None` to wait forever.  Defaults to ten minutes.
+    pub fn idle_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.idle_timeout = timeout;
+        self
+    }
+
+    /// Set the width to which output is word-wrapped.  Defaults to 0, which
+    /// disables wrapping unless Telnet is in use, in which case output is
+    /// wrapped to the width of the client's window if it reports it.
+    pub fn wrap_width(mut self, width: usize) -> Self {
+        self.wrap_width = width;
+        self
+    }
+
+    /// Whether to talk to clients using the Telnet protocol via a
+    /// [`TelnetInterface`](crate::TelnetInterface).  Defaults to false.
+    pub fn telnet(mut self, telnet: bool) -> Self {
+        self.telnet = telnet;
+        self
+    }
+
+    /// Accept connections on `listener` forever, starting a new game from a
+    /// clone of `game` for each one.  Failures to accept a connection are
+    /// reported on stderr and otherwise ignored.
+    pub fn serve_tcp<G>(&self, listener: &TcpListener, game: G) -> !
+    where
+        G: GameBuilder + Clone + Send + 'static,
+    {
+        let active = Arc::new(AtomicUsize::new(0));
+        loop {
+            match listener.accept() {
+                Ok((stream, _)) => {
+                    self.spawn_session(stream, game.clone(), &active, Server::run_session);
+                }
+                Err(e) => accept_failed(&e),
+            }
+        }
+    }
+
+    /// Accept connections on `listener` forever, starting a new game from a
+    /// clone of `game` for each one.  Failures to accept a connection are
+    /// reported on stderr and otherwise ignored.
+    #[cfg(unix)]
+    pub fn serve_unix<G>(&self, listener: &std::os::unix::net::UnixListener, game: G) -> !
+    where
+        G: GameBuilder + Clone + Send + 'static,
+    {
+        let active = Arc::new(AtomicUsize::new(0));
+        loop {
+            match listener.accept() {
+                Ok((stream, _)) => {
+                    self.spawn_session(stream, game.clone(), &active, Server::run_session);
+                }
+                Err(e) => accept_failed(&e),
+            }
+        }
+    }
+
+    /// Accept HTTP connections on `listener` forever, serving a web page for
+    /// playing in a browser at `/` and starting a new game from a clone of
+    /// `game` for each WebSocket connection to `/ws`.  Failures to accept a
+    /// connection are reported on stderr and otherwise ignored.
+    ///
+    /// The page is self-contained, so it works without Internet access.  Only
+    /// WebSocket connections from pages served by this server are accepted,
+    /// and the server must be addressed as `localhost` or by IP address rather
+    /// than by another domain name.  The Telnet setting is ignored.
+    #[cfg(feature = "web")]
+    pub fn serve_web<G>(&self, listener: &TcpListener, game: G) -> !
+    where
+        G: GameBuilder + Clone + Send + 'static,
+    {
+        let active = Arc::new(AtomicUsize::new(0));
+        loop {
+            match listener.accept() {
+                Ok((stream, _)) => {
+                    self.spawn_session(stream, game.clone(), &active, |server, stream, game| {
+                        stream.set_read_timeout(server.idle_timeout)?;
+                        crate::web::handle_connection(stream, game, server.wrap_width)
+                    });
+                }
+                Err(e) => accept_failed(&e),
+            }
+        }
+    }
+
+    fn spawn_session<S, G>(
+        &self,
+        mut stream: S,
+        game: G,
+        active: &Arc<AtomicUsize>,
+        run: fn(&Server, S, G) -> io::Result<()>,
+    ) where
+        S: Connection,
+        G: GameBuilder + Clone + Send + 'static,
+    {
+        if active.fetch_add(1, Ordering::SeqCst) >= self.max_connections {
+            active.fetch_sub(1, Ordering::SeqCst);
+            // The client is being turned away regardless, so there's nothing
+            // to do if this fails.
+            let _ = writeln!(
+                stream,
+                "Sorry, the server is full.  Please try again later."
+            );
+            return;
+        }
+        let guard = SessionGuard(Arc::clone(active));
+        let server = *self;
+        std::thread::spawn(move || {
+            // Hold the connection open until the session is no longer counted
+            // as active, so that a client that sees it close can reconnect
+            // straight away.
+            let conn = stream.try_clone();
+            // Errors here are almost always due to the client disconnecting,
+            // which just means the session is over.
+            let _ = run(&server, stream, game);
+            drop(guard);
+            drop(conn);
+        });
+    }
+
+    fn run_session<S: Connection, G: GameBuilder + Clone>(
+        &self,
+        stream: S,
+        game: G,
+    ) -> io::Result<()> {
+        stream.set_read_timeout(self.idle_timeout)?;
+        let reader = BufReader::new(stream.try_clone()?);
+        if self.telnet {
+            let iface = TelnetInterfaceBuilder::new(reader, stream).wrap_width(self.wrap_width);
+            play_game(SocketInterfaceBuilder(iface), game, false)
+        } else {
+            let iface = SocketInterfaceBuilder(BasicInterfaceBuilder::new(reader, stream));
+            play_game(
+                WrapInterfaceBuilder::new(iface, self.wrap_width),
+                game,
+                false,
+            )
+        }
+    }
+}
+
+/// How long to wait after failing to accept a connection before trying again
+const ACCEPT_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+// Report a failure to accept a connection.  These are usually temporary (e.g.,
+// running out of file descriptors), so the server carries on after a pause
+// that keeps it from spinning if the problem persists.
+fn accept_failed(e: &io::Error) {
+    eprintln!("Failed to accept connection: {e}");
+    std::thread::sleep(ACCEPT_RETRY_DELAY);
+}
+
+impl Default for Server {
+    fn default() -> Self {
+        Server::new()
+    }
+}
+
+/// A stream socket that a game session can be run over
+trait Connection: Read + Write + Send + Sized + 'static {
+    fn try_clone(&self) -> io::Result<Self>;
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()>;
+}
+
+impl Connection for TcpStream {
+    fn try_clone(&self) -> io::Result<Self> {
+        TcpStream::try_clone(self)
+    }
+
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        TcpStream::set_read_timeout(self, timeout)
+    }
+}
+
+#[cfg(unix)]
+impl Connection for std::os::unix::net::UnixStream {
+    fn try_clone(&self) -> io::Result<Self> {
+        std::os::unix::net::UnixStream::try_clone(self)
+    }
+
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        std::os::unix::net::UnixStream::set_read_timeout(self, timeout)
+    }
+}
+
+// Decrements the count of active sessions when a session thread ends, however
+// it ends
+#[derive(Debug)]
+struct SessionGuard(Arc<AtomicUsize>);
+
+impl Drop for SessionGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[derive(Debug)]
+struct SocketInterfaceBuilder<B>(B);
+
+impl<B: InterfaceBuilder> InterfaceBuilder for SocketInterfaceBuilder<B> {
+    type Interface = SocketInterface<B::Interface>;
+
+    fn build_interface(self) -> io::Result<Self::Interface> {
+        self.0.build_interface().map(SocketInterface)
+    }
+}
+
+// An interface over a socket that treats timeouts and dropped connections
+// (including ones that drop partway through a line) as the end of input
+#[derive(Debug)]
+struct SocketInterface<I>(I);
+
+impl<I: Interface> Interface for SocketInterface<I> {
+    fn show_output(&mut self, text: &str) -> io::Result<()> {
+        self.0.show_output(text)
+    }
+
+    fn show_output_with_context(&mut self, text: &str, ctx: &OutputContext) -> io::Result<()> {
+        self.0.show_output_with_context(text, ctx)
+    }
+
+    fn show_messages(&mut self, messages: &[Message], ctx: &OutputContext) -> io::Result<()> {
+        self.0.show_messages(messages, ctx)
+    }
+
+    fn get_input(&mut self) -> io::Result<Option<String>> {
+        match self.0.get_input() {
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                self.0
+                    .show_output("You have been idle for too long.  Goodbye.")?;
+                Ok(None)
+            }
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    ErrorKind::ConnectionReset | ErrorKind::ConnectionAborted
+                ) =>
+            {
+                Ok(None)
+            }
+            Ok(Some(line)) if !line.ends_with('\n') => Ok(None),
+            r => r,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::Echo;
+    use std::net::SocketAddr;
+
+    fn start_server(server: Server) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || server.serve_tcp(&listener, Echo));
+        addr
+    }
+
+    // Read from `stream` until the received text ends with `end` or the
+    // connection is closed
+    fn read_until(stream: &mut TcpStream, end: &str) -> String {
+        String::from_utf8(read_bytes_until(stream, end.as_bytes())).unwrap()
+    }
+
+    fn read_bytes_until(stream: &mut TcpStream, end: &[u8]) -> Vec<u8> {
+        let mut buf = Vec::new();
+        let mut byte = [0u8; 1];
+        while !buf.ends_with(end) {
+            match stream.read(&mut byte) {
+                Ok(0) => break,
+                Ok(_) => buf.push(byte[0]),
+                Err(e) => panic!("read failed: {e}"),
+            }
+        }
+        buf
+    }
+
+    #[test]
+    fn test_session() {
+        let addr = start_server(Server::new());
+        let mut a = TcpStream::connect(addr).unwrap();
+        let mut b = TcpStream::connect(addr).unwrap();
+        assert_eq!(read_until(&mut a, "> "), "Hello.\n\n> ");
+        assert_eq!(read_until(&mut b, "> "), "Hello.\n\n> ");
+        a.write_all(b"look\n").unwrap();
+        assert_eq!(read_until(&mut a, "> "), "\nYou said: look\n\n> ");
+        b.write_all(b"quit\r\n").unwrap();
+        assert_eq!(read_until(&mut b, "\n\n"), "\nBye.\n");
+        a.write_all(b"again\n").unwrap();
+        assert_eq!(read_until(&mut a, "> "), "\nYou said: again\n\n> ");
+        a.write_all(b"save\n").unwrap();
+        assert_eq!(
+            read_until(&mut a, "> "),
+            "\nSaving is not available here.\n\n> "
+        );
+    }
+
+    #[test]
+    fn test_max_connections() {
+        let addr = start_server(Server::new().max_connections(1));
+        let mut a = TcpStream::connect(addr).unwrap();
+        assert_eq!(read_until(&mut a, "> "), "Hello.\n\n> ");
+        let mut b = TcpStream::connect(addr).unwrap();
+        assert_eq!(
+            read_until(&mut b, "\n"),
+            "Sorry, the server is full.  Please try again later.\n"
+        );
+        a.write_all(b"quit\n").unwrap();
+        // The connection is closed once the session has finished.
+        assert_eq!(read_until(&mut a, "\0"), "\nBye.\n");
+        let mut c = TcpStream::connect(addr).unwrap();
+        assert_eq!(read_until(&mut c, "> "), "Hello.\n\n> ");
+    }
+
+    #[test]
+    fn test_idle_timeout() {
+        let addr = start_server(Server::new().idle_timeout(Some(Duration::from_millis(100))));
+        let mut a = TcpStream::connect(addr).unwrap();
+        assert_eq!(read_until(&mut a, "> "), "Hello.\n\n> ");
+        assert_eq!(
+            read_until(&mut a, "\0"),
+            "\nYou have been idle for too long.  Goodbye.\n"
+        );
+    }
+
+    #[test]
+    fn test_telnet() {
+        // The prompt followed by IAC GA
+        const PROMPT: &[u8] = b"> \xFF\xF9";
+        let addr = start_server(Server::new().telnet(true));
+        let mut a = TcpStream::connect(addr).unwrap();
+        assert_eq!(
+            read_bytes_until(&mut a, PROMPT),
+            b"\xFF\xFD\x1F\xFF\xFD\x22Hello.\r\n\r\n> \xFF\xF9"
+        );
+        // Report a window width of 10 columns.
+        a.write_all(b"\xFF\xFB\x1F\xFF\xFA\x1F\x00\x0A\x00\x18\xFF\xF0look around\r\n")
+            .unwrap();
+        assert_eq!(
+            read_bytes_until(&mut a, PROMPT),
+            b"\r\nYou said:\r\nlook\r\naround\r\n\r\n> \xFF\xF9"
+        );
+    }
+
+    #[test]
+    fn test_disconnect() {
+        let addr = start_server(Server::new().max_connections(1));
+        let mut a = TcpStream::connect(addr).unwrap();
+        assert_eq!(read_until(&mut a, "> "), "Hello.\n\n> ");
+        a.write_all(b"lo").unwrap();
+        a.shutdown(std::net::Shutdown::Write).unwrap();
+        // The connection is closed once the session has finished.
+        assert_eq!(read_until(&mut a, "\0"), "");
+        let mut b = TcpStream::connect(addr).unwrap();
+        assert_eq!(read_until(&mut b, "> "), "Hello.\n\n> ");
+    }
+}
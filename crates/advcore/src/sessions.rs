@@ -0,0 +1,620 @@
+use crate::interface::OutputContext;
+use crate::{GameBuilder, GameEngine, Message, Output, join_messages, respond};
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::io::{self, ErrorKind};
+use std::num::ParseIntError;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+/// The period over which [`SessionMetrics::commands_per_second`] is averaged
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// A [`GameEngine`] whose state can be written out as text and read back in
+/// later, allowing a [`SessionManager`] to move idle games out of memory
+pub trait SavableEngine: GameEngine {
+    type Error: std::error::Error + Send + Sync + 'static;
+
+    /// Serialize the game's state.
+    fn save(&self) -> String;
+
+    /// Reconstruct a game from the output of [`SavableEngine::save()`].
+    fn restore(data: &str) -> Result<Self, Self::Error>;
+}
+
+/// An identifier for a session in a [`SessionManager`]
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct SessionId(u64);
+
+impl fmt::Display for SessionId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for SessionId {
+    type Err = ParseIntError;
+
+    fn from_str(s: &str) -> Result<SessionId, ParseIntError> {
+        s.parse::<u64>().map(SessionId)
+    }
+}
+
+/// The result of starting a session or sending it input
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Reply {
+    pub messages: Vec<Message>,
+    /// If `context.ended` is true, the session is over and has been removed
+    /// from the manager.
+    pub context: OutputContext,
+}
+
+impl Reply {
+    /// Returns the text of all of the messages joined together with newlines
+    pub fn text(&self) -> String {
+        join_messages(&self.messages)
+    }
+}
+
+/// A snapshot of a [`SessionManager`]'s activity
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SessionMetrics {
+    /// The number of sessions whose games are in memory
+    pub active: usize,
+    /// The number of sessions whose games have been evicted to disk
+    pub evicted: usize,
+    /// The number of sessions started since the manager was created
+    pub started: u64,
+    /// The number of commands handled since the manager was created
+    pub commands: u64,
+    /// The average number of commands handled per second over the last
+    /// minute
+    pub commands_per_second: f64,
+}
+
+/// Hosts many independent games at once, keyed by [`SessionId`]
+///
+/// A `SessionManager` is meant to be shared between threads (e.g., in an
+/// [`Arc`]) by whatever is accepting input from players, such as an HTTP
+/// front end, which can call [`SessionManager::start_session()`] and
+/// [`SessionManager::handle_input()`] directly.  Code that works with
+/// [`GameBuilder`]s, such as [`Server`](crate::Server), can use
+/// [`SessionManager::game()`] instead.
+///
+/// Games that have not received input for a while can be saved to a
+/// directory and dropped from memory by calling
+/// [`SessionManager::evict_idle()`] periodically; they are transparently
+/// restored the next time they receive input.
+#[derive(Debug)]
+pub struct SessionManager<G: GameBuilder> {
+    game: G,
+    dir: PathBuf,
+    idle_timeout: Duration,
+    next_id: AtomicU64,
+    sessions: Mutex<HashMap<SessionId, SharedSession<G::Engine>>>,
+    stats: Mutex<Stats>,
+}
+
+impl<G> SessionManager<G>
+where
+    G: GameBuilder + Clone,
+    G::Engine: SavableEngine,
+{
+    /// Create a manager that starts each session from a clone of `game` and
+    /// evicts idle games to files in `dir`
+    pub fn new<P: Into<PathBuf>>(game: G, dir: P) -> Self {
+        SessionManager {
+            game,
+            dir: dir.into(),
+            idle_timeout: Duration::from_secs(300),
+            next_id: AtomicU64::new(1),
+            sessions: Mutex::new(HashMap::new()),
+            stats: Mutex::new(Stats::default()),
+        }
+    }
+
+    /// Set how long a game must go without input before
+    /// [`SessionManager::evict_idle()`] moves it to disk.  Defaults to five
+    /// minutes.
+    pub fn idle_timeout(mut self, timeout: Duration) -> Self {
+        self.idle_timeout = timeout;
+        self
+    }
+
+    /// Start a new session and return its ID along with the game's opening
+    /// text.  If the game ends immediately, the session is not kept.
+    pub fn start_session(&self) -> (SessionId, Reply) {
+        let id = SessionId(self.next_id.fetch_add(1, Ordering::Relaxed));
+        lock(&self.stats).started += 1;
+        let r = self.game.clone().start();
+        let reply = Reply {
+            messages: r.messages().to_vec(),
+            context: r.context(0),
+        };
+        if let Some((game, confirming)) = r.into_pending() {
+            let session = Session {
+                state: State::Active(game),
+                confirming,
+                turn: 0,
+                last_active: Instant::now(),
+            };
+            lock(&self.sessions).insert(id, Arc::new(Mutex::new(session)));
+        }
+        (id, reply)
+    }
+
+    /// Pass a line of input to the game in the given session, restoring it
+    /// from disk first if it was evicted
+    pub fn handle_input(&self, id: SessionId, input: &str) -> Result<Reply, SessionError> {
+        let session = self.get(id)?;
+        let mut session = lock(&session);
+        let game = match std::mem::replace(&mut session.state, State::Ended) {
+            State::Active(game) => game,
+            State::Evicted => match self.load(id) {
+                Ok(game) => game,
+                Err(e) => {
+                    // Leave the file in place so that it can be retried.
+                    session.state = State::Evicted;
+                    return Err(SessionError::Io(e));
+                }
+            },
+            State::Ended => return Err(SessionError::NoSuchSession(id)),
+        };
+        // Sessions are hosted for other people, who have no business writing
+        // to the host's files.
+        let r = respond(game, session.confirming, input).save_if_requested(false);
+        session.turn += 1;
+        session.last_active = Instant::now();
+        lock(&self.stats).record_command(session.last_active);
+        let reply = Reply {
+            messages: r.messages().to_vec(),
+            context: r.context(session.turn),
+        };
+        if let Some((game, confirming)) = r.into_pending() {
+            session.state = State::Active(game);
+            session.confirming = confirming;
+        } else {
+            drop(session);
+            lock(&self.sessions).remove(&id);
+        }
+        Ok(reply)
+    }
+
+    /// End a session, discarding its game
+    pub fn end_session(&self, id: SessionId) -> Result<(), SessionError> {
+        let session = lock(&self.sessions)
+            .remove(&id)
+            .ok_or(SessionError::NoSuchSession(id))?;
+        let old = std::mem::replace(&mut lock(&session).state, State::Ended);
+        if matches!(old, State::Evicted) {
+            std::fs::remove_file(self.path(id))?;
+        }
+        Ok(())
+    }
+
+    /// Save every game that has gone without input for longer than the idle
+    /// timeout to disk and drop it from memory.  Sessions that are busy
+    /// handling input are skipped.  Returns the number of games evicted.
+    pub fn evict_idle(&self) -> io::Result<usize> {
+        let sessions = lock(&self.sessions)
+            .iter()
+            .map(|(&id, s)| (id, Arc::clone(s)))
+            .collect::<Vec<_>>();
+        let mut evicted = 0;
+        for (id, session) in sessions {
+            let Ok(mut session) = session.try_lock() else {
+                continue;
+            };
+            if session.last_active.elapsed() < self.idle_timeout {
+                continue;
+            }
+            if let State::Active(game) = &session.state {
+                if evicted == 0 {
+                    std::fs::create_dir_all(&self.dir)?;
+                }
+                std::fs::write(self.path(id), game.save())?;
+                session.state = State::Evicted;
+                evicted += 1;
+            }
+        }
+        Ok(evicted)
+    }
+
+    pub fn metrics(&self) -> SessionMetrics {
+        let (mut active, mut evicted) = (0, 0);
+        for session in lock(&self.sessions).values() {
+            match lock(session).state {
+                State::Active(_) => active += 1,
+                State::Evicted => evicted += 1,
+                State::Ended => (),
+            }
+        }
+        let mut stats = lock(&self.stats);
+        stats.prune(Instant::now());
+        // Saturate rather than lose precision in the unlikely event of over
+        // four billion commands a minute
+        let recent = u32::try_from(stats.recent.len()).unwrap_or(u32::MAX);
+        SessionMetrics {
+            active,
+            evicted,
+            started: stats.started,
+            commands: stats.commands,
+            commands_per_second: f64::from(recent) / RATE_WINDOW.as_secs_f64(),
+        }
+    }
+
+    /// Return a [`GameBuilder`] that starts a new session in this manager
+    /// each time it's used, for running sessions with
+    /// [`run_game()`](crate::run_game) or a [`Server`](crate::Server).  The
+    /// session ends when the game does or when the engine is dropped.
+    pub fn game(self: &Arc<Self>) -> SessionGame<G> {
+        SessionGame(Arc::clone(self))
+    }
+
+    fn get(&self, id: SessionId) -> Result<SharedSession<G::Engine>, SessionError> {
+        lock(&self.sessions)
+            .get(&id)
+            .cloned()
+            .ok_or(SessionError::NoSuchSession(id))
+    }
+
+    fn load(&self, id: SessionId) -> io::Result<G::Engine> {
+        let path = self.path(id);
+        let data = std::fs::read_to_string(&path)?;
+        let game =
+            G::Engine::restore(&data).map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?;
+        std::fs::remove_file(&path)?;
+        Ok(game)
+    }
+
+    fn path(&self, id: SessionId) -> PathBuf {
+        session_path(&self.dir, id)
+    }
+}
+
+fn session_path(dir: &Path, id: SessionId) -> PathBuf {
+    dir.join(format!("{id}.sav"))
+}
+
+// Lock a mutex, ignoring poisoning; a session whose game panicked is left in
+// the `Ended` state, so the data behind the lock is always usable.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+type SharedSession<E> = Arc<Mutex<Session<E>>>;
+
+#[derive(Debug)]
+struct Session<E> {
+    state: State<E>,
+    // Whether the game is waiting for the answer to a question
+    confirming: bool,
+    turn: u64,
+    last_active: Instant,
+}
+
+#[derive(Debug)]
+enum State<E> {
+    Active(E),
+    Evicted,
+    Ended,
+}
+
+#[derive(Debug, Default)]
+struct Stats {
+    started: u64,
+    commands: u64,
+    // The times of the commands handled within the last `RATE_WINDOW`
+    recent: VecDeque<Instant>,
+}
+
+impl Stats {
+    fn record_command(&mut self, when: Instant) {
+        self.commands += 1;
+        self.recent.push_back(when);
+        self.prune(when);
+    }
+
+    fn prune(&mut self, now: Instant) {
+        while self
+            .recent
+            .front()
+            .is_some_and(|&t| now.duration_since(t) > RATE_WINDOW)
+        {
+            self.recent.pop_front();
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum SessionError {
+    NoSuchSession(SessionId),
+    Io(io::Error),
+}
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SessionError::NoSuchSession(id) => write!(f, "no such session: {id}"),
+            SessionError::Io(e) => write!(f, "could not restore session: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for SessionError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SessionError::NoSuchSession(_) => None,
+            SessionError::Io(e) => Some(e),
+        }
+    }
+}
+
+impl From<io::Error> for SessionError {
+    fn from(e: io::Error) -> SessionError {
+        SessionError::Io(e)
+    }
+}
+
+/// A [`GameBuilder`] that starts sessions in a [`SessionManager`]; see
+/// [`SessionManager::game()`]
+pub struct SessionGame<G: GameBuilder>(Arc<SessionManager<G>>);
+
+impl<G: GameBuilder> fmt::Debug for SessionGame<G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("SessionGame").finish_non_exhaustive()
+    }
+}
+
+impl<G: GameBuilder> Clone for SessionGame<G> {
+    fn clone(&self) -> Self {
+        SessionGame(Arc::clone(&self.0))
+    }
+}
+
+impl<G> GameBuilder for SessionGame<G>
+where
+    G: GameBuilder + Clone,
+    G::Engine: SavableEngine,
+{
+    type Engine = SessionEngine<G>;
+
+    fn start(self) -> Output<SessionEngine<G>> {
+        let (id, reply) = self.0.start_session();
+        SessionEngine {
+            ticket: Ticket {
+                manager: self.0,
+                id,
+            },
+            location: None,
+            status: None,
+        }
+        .output(reply)
+    }
+}
+
+/// A handle to a game running in a [`SessionManager`]
+#[derive(Debug)]
+pub struct SessionEngine<G>
+where
+    G: GameBuilder + Clone,
+    G::Engine: SavableEngine,
+{
+    ticket: Ticket<G>,
+    location: Option<String>,
+    status: Option<String>,
+}
+
+impl<G> SessionEngine<G>
+where
+    G: GameBuilder + Clone,
+    G::Engine: SavableEngine,
+{
+    pub fn id(&self) -> SessionId {
+        self.ticket.id
+    }
+
+    fn output(mut self, reply: Reply) -> Output<Self> {
+        if reply.context.ended {
+            Output::Goodbye {
+                messages: reply.messages,
+            }
+        } else {
+            self.location = reply.context.location;
+            self.status = reply.context.status;
+            Output::Continue {
+                game: self,
+                messages: reply.messages,
+            }
+        }
+    }
+}
+
+impl<G> GameEngine for SessionEngine<G>
+where
+    G: GameBuilder + Clone,
+    G::Engine: SavableEngine,
+{
+    fn handle_input(self, input: &str) -> Output<Self> {
+        match self.ticket.manager.handle_input(self.ticket.id, input) {
+            Ok(reply) => self.output(reply),
+            // The game is still on disk, so keep the session (and with it the
+            // file) around in case restoring it works next time.
+            Err(e @ SessionError::Io(_)) => Output::Continue {
+                game: self,
+                messages: vec![Message::system(format!("Error: {e}"))],
+            },
+            Err(e) => Output::goodbye(format!("Error: {e}")),
+        }
+    }
+
+    fn location(&self) -> Option<String> {
+        self.location.clone()
+    }
+
+    fn status(&self) -> Option<String> {
+        self.status.clone()
+    }
+}
+
+// Ends its session when dropped
+struct Ticket<G>
+where
+    G: GameBuilder + Clone,
+    G::Engine: SavableEngine,
+{
+    manager: Arc<SessionManager<G>>,
+    id: SessionId,
+}
+
+impl<G> fmt::Debug for Ticket<G>
+where
+    G: GameBuilder + Clone,
+    G::Engine: SavableEngine,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Ticket")
+            .field("id", &self.id)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<G> Drop for Ticket<G>
+where
+    G: GameBuilder + Clone,
+    G::Engine: SavableEngine,
+{
+    fn drop(&mut self) {
+        // The session will already be gone if the game ended normally.
+        let _ = self.manager.end_session(self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::Counter;
+    use crate::{BasicInterfaceBuilder, run_game};
+
+    #[test]
+    fn test_confirm() {
+        let manager = SessionManager::new(Counter(0), tempdir("confirm"));
+        let (id, _) = manager.start_session();
+        manager.handle_input(id, "go").unwrap();
+        let ask = |input| manager.handle_input(id, input).unwrap().text();
+        assert_eq!(ask("reset"), "Really reset?");
+        assert_eq!(ask("go"), "Please answer yes or no.");
+        assert_eq!(ask("No"), "1");
+        assert_eq!(ask("go"), "2");
+        assert_eq!(ask("reset"), "Really reset?");
+        assert_eq!(ask(" y\n"), "0");
+    }
+
+    fn tempdir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("advcore-sessions-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_sessions() {
+        let dir = tempdir("sessions");
+        let manager = SessionManager::new(Counter(0), &dir);
+        let (a, reply) = manager.start_session();
+        assert_eq!(reply.text(), "Count!");
+        assert_eq!(reply.context.location.as_deref(), Some("Room 0"));
+        let (b, _) = manager.start_session();
+        assert_ne!(a, b);
+        assert_eq!(manager.handle_input(a, "go").unwrap().text(), "1");
+        let reply = manager.handle_input(a, "go").unwrap();
+        assert_eq!(reply.text(), "2");
+        assert_eq!(reply.context.turn, 2);
+        assert_eq!(manager.handle_input(b, "go").unwrap().text(), "1");
+        let reply = manager.handle_input(b, "stop").unwrap();
+        assert_eq!(reply.text(), "Final count: 1");
+        assert!(reply.context.ended);
+        assert!(matches!(
+            manager.handle_input(b, "go"),
+            Err(SessionError::NoSuchSession(id)) if id == b
+        ));
+        let metrics = manager.metrics();
+        assert_eq!(metrics.active, 1);
+        assert_eq!(metrics.evicted, 0);
+        assert_eq!(metrics.started, 2);
+        assert_eq!(metrics.commands, 4);
+        assert!(metrics.commands_per_second > 0.0);
+        manager.end_session(a).unwrap();
+        assert_eq!(manager.metrics().active, 0);
+        assert!(manager.end_session(a).is_err());
+    }
+
+    #[test]
+    fn test_evict_idle() {
+        let dir = tempdir("evict");
+        let manager = SessionManager::new(Counter(0), &dir);
+        let (a, _) = manager.start_session();
+        manager.handle_input(a, "go").unwrap();
+        assert_eq!(manager.evict_idle().unwrap(), 0);
+        let manager = manager.idle_timeout(Duration::ZERO);
+        assert_eq!(manager.evict_idle().unwrap(), 1);
+        assert_eq!(std::fs::read_to_string(session_path(&dir, a)).unwrap(), "1");
+        let metrics = manager.metrics();
+        assert_eq!(metrics.active, 0);
+        assert_eq!(metrics.evicted, 1);
+        let reply = manager.handle_input(a, "go").unwrap();
+        assert_eq!(reply.text(), "2");
+        assert_eq!(reply.context.turn, 2);
+        assert!(!session_path(&dir, a).exists());
+        assert_eq!(manager.evict_idle().unwrap(), 1);
+        manager.end_session(a).unwrap();
+        assert!(!session_path(&dir, a).exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_session_game_restore_error() {
+        let dir = tempdir("game-restore");
+        let manager = Arc::new(SessionManager::new(Counter(0), &dir).idle_timeout(Duration::ZERO));
+        let game = manager.game().start().into_game().unwrap();
+        let path = session_path(&dir, game.id());
+        assert_eq!(manager.evict_idle().unwrap(), 1);
+        std::fs::write(&path, "garbage").unwrap();
+        let r = game.handle_input("go");
+        assert_eq!(
+            r.text(),
+            "Error: could not restore session: invalid digit found in string"
+        );
+        let Output::Continue { game, .. } = r else {
+            panic!("session ended after failing to restore");
+        };
+        assert!(path.exists());
+        std::fs::write(&path, "5").unwrap();
+        assert_eq!(game.handle_input("go").text(), "6");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_session_game() {
+        let manager = Arc::new(SessionManager::new(Counter(0), tempdir("game")));
+        let mut output = Vec::new();
+        let iface = BasicInterfaceBuilder::new(&b"go\nstop\n"[..], &mut output);
+        run_game(iface, manager.game()).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "Count!\n\n> \n1\n\n> \nFinal count: 1\n"
+        );
+        // A session whose engine is dropped without the game ending is
+        // removed too.
+        let mut output = Vec::new();
+        let iface = BasicInterfaceBuilder::new(&b"go\n"[..], &mut output);
+        run_game(iface, manager.game()).unwrap();
+        let metrics = manager.metrics();
+        assert_eq!(metrics.active, 0);
+        assert_eq!(metrics.started, 2);
+        assert_eq!(metrics.commands, 3);
+    }
+}
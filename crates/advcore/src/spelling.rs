@@ -0,0 +1,220 @@
+/// Settings for correcting misspelled words against a game's vocabulary.
+///
+/// Distances are measured in half-edits: inserting, deleting, or substituting
+/// a character costs 2, while the more common typos — substituting a key that
+/// is adjacent on a QWERTY keyboard, transposing two neighboring characters,
+/// or doubling or undoubling a letter — cost 1.  Comparisons are
+/// ASCII-case-insensitive.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Speller {
+    /// Candidates farther than this from the input are never considered
+    pub max_distance: u32,
+
+    /// Input words shorter than this (in characters) are never corrected
+    pub min_length: usize,
+
+    /// Whether to silently replace the input with the closest candidate when
+    /// there is exactly one; if false, it is only suggested
+    pub auto_correct: bool,
+
+    /// The maximum number of candidates to suggest
+    pub max_suggestions: usize,
+}
+
+impl Speller {
+    pub fn new() -> Self {
+        Speller {
+            max_distance: 2,
+            min_length: 3,
+            auto_correct: true,
+            max_suggestions: 3,
+        }
+    }
+
+    /// Find the words in `vocabulary` that `word` may have been a misspelling
+    /// of
+    pub fn correct<'a, I>(&self, word: &str, vocabulary: I) -> Correction<'a>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        if word.chars().count() < self.min_length {
+            return Correction::Unknown;
+        }
+        let mut candidates = vocabulary
+            .into_iter()
+            .filter_map(|v| {
+                let d = distance(word, v);
+                (d <= self.max_distance).then_some((d, v))
+            })
+            .collect::<Vec<_>>();
+        candidates.sort_unstable();
+        candidates.dedup();
+        match candidates.as_slice() {
+            [] => Correction::Unknown,
+            [(_, v)] if self.auto_correct => Correction::Replace(v),
+            [(d1, v), (d2, _), ..] if self.auto_correct && d1 < d2 => Correction::Replace(v),
+            _ => Correction::Suggest(
+                candidates
+                    .into_iter()
+                    .take(self.max_suggestions)
+                    .map(|(_, v)| v)
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl Default for Speller {
+    fn default() -> Self {
+        Speller::new()
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Correction<'a> {
+    /// The input should be replaced with the given word
+    Replace(&'a str),
+    /// The input may have been meant to be one of the given words, in order
+    /// of decreasing likelihood
+    Suggest(Vec<&'a str>),
+    /// No vocabulary word resembles the input
+    Unknown,
+}
+
+/// Compute the weighted edit distance between two words as described in the
+/// documentation for [`Speller`]
+pub fn distance(a: &str, b: &str) -> u32 {
+    let a = a
+        .chars()
+        .map(|c| c.to_ascii_lowercase())
+        .collect::<Vec<_>>();
+    let b = b
+        .chars()
+        .map(|c| c.to_ascii_lowercase())
+        .collect::<Vec<_>>();
+    // Cost of deleting `s[i]` (or, equivalently, inserting it into the other
+    // string)
+    let indel = |s: &[char], i: usize| {
+        if i > 0 && s.get(i - 1) == s.get(i) {
+            1
+        } else {
+            2
+        }
+    };
+    // `d[i][j]` is the distance between `a[..i]` and `b[..j]`.
+    let mut d = vec![vec![0u32; b.len() + 1]; a.len() + 1];
+    for i in 1..=a.len() {
+        d[i][0] = d[i - 1][0] + indel(&a, i - 1);
+    }
+    for j in 1..=b.len() {
+        d[0][j] = d[0][j - 1] + indel(&b, j - 1);
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let (x, y) = (a[i - 1], b[j - 1]);
+            let subst = if x == y {
+                0
+            } else if adjacent_keys(x, y) {
+                1
+            } else {
+                2
+            };
+            let mut best = (d[i - 1][j] + indel(&a, i - 1))
+                .min(d[i][j - 1] + indel(&b, j - 1))
+                .min(d[i - 1][j - 1] + subst);
+            if i > 1 && j > 1 && x == b[j - 2] && a[i - 2] == y {
+                best = best.min(d[i - 2][j - 2] + 1);
+            }
+            d[i][j] = best;
+        }
+    }
+    d[a.len()][b.len()]
+}
+
+/// Returns true if the lowercase letters `x` and `y` are next to each other on
+/// a QWERTY keyboard
+fn adjacent_keys(x: char, y: char) -> bool {
+    // Horizontal positions are doubled so that the stagger between rows can
+    // be represented with integers.
+    fn position(c: char) -> Option<(i32, i32)> {
+        ["qwertyuiop", "asdfghjkl", "zxcvbnm"]
+            .into_iter()
+            .zip(0..)
+            .find_map(|(keys, row)| {
+                let col = keys.chars().position(|k| k == c)?;
+                let col = i32::try_from(col).ok()?;
+                Some((row, 2 * col + row))
+            })
+    }
+    match (position(x), position(y)) {
+        (Some((r1, c1)), Some((r2, c2))) => {
+            (r1 == r2 && (c1 - c2).abs() == 2) || ((r1 - r2).abs() == 1 && (c1 - c2).abs() == 1)
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_distance() {
+        assert_eq!(distance("GLOBE", "globe"), 0);
+        assert_eq!(distance("GLOBB", "GLOBE"), 2);
+        assert_eq!(distance("GLOVE", "GLOBE"), 1);
+        assert_eq!(distance("GLBOE", "GLOBE"), 1);
+        assert_eq!(distance("GLOBBE", "GLOBE"), 1);
+        assert_eq!(distance("GLBE", "GLOBE"), 2);
+        assert_eq!(distance("", "AT"), 4);
+    }
+
+    #[test]
+    fn test_adjacent_keys() {
+        assert!(adjacent_keys('a', 'q'));
+        assert!(adjacent_keys('a', 'w'));
+        assert!(adjacent_keys('a', 's'));
+        assert!(adjacent_keys('a', 'z'));
+        assert!(adjacent_keys('x', 's'));
+        assert!(adjacent_keys('x', 'd'));
+        assert!(!adjacent_keys('a', 'e'));
+        assert!(!adjacent_keys('a', 'x'));
+        assert!(!adjacent_keys('-', '='));
+    }
+
+    #[test]
+    fn test_correct() {
+        let vocab = ["GLOBE", "GLOVE", "BOOK", "BOOKS", "LOOK"];
+        let speller = Speller::new();
+        assert_eq!(
+            speller.correct("globb", vocab),
+            Correction::Replace("GLOBE")
+        );
+        assert_eq!(
+            speller.correct("GLOBR", vocab),
+            Correction::Replace("GLOBE")
+        );
+        assert_eq!(
+            speller.correct("GLOXE", vocab),
+            Correction::Suggest(vec!["GLOBE", "GLOVE"])
+        );
+        assert_eq!(
+            speller.correct("BOOKZ", vocab),
+            Correction::Replace("BOOKS")
+        );
+        assert_eq!(
+            speller.correct("COOK", vocab),
+            Correction::Suggest(vec!["BOOK", "LOOK"])
+        );
+        assert_eq!(speller.correct("XYZZY", vocab), Correction::Unknown);
+        assert_eq!(speller.correct("GL", vocab), Correction::Unknown);
+        let speller = Speller {
+            auto_correct: false,
+            ..Speller::new()
+        };
+        assert_eq!(
+            speller.correct("globb", vocab),
+            Correction::Suggest(vec!["GLOBE"])
+        );
+    }
+}
@@ -0,0 +1,386 @@
+use crate::interface::{Interface, InterfaceBuilder};
+use crate::wrap::wrap;
+use std::collections::{HashSet, VecDeque};
+use std::io::{self, BufRead, Write};
+
+// Telnet command bytes (RFC 854)
+const IAC: u8 = 255;
+const DONT: u8 = 254;
+const DO: u8 = 253;
+const WONT: u8 = 252;
+const WILL: u8 = 251;
+const SB: u8 = 250;
+const GA: u8 = 249;
+const SE: u8 = 240;
+
+// Telnet options
+/// Negotiate About Window Size (RFC 1073)
+const NAWS: u8 = 31;
+/// Line mode (RFC 1184)
+const LINEMODE: u8 = 34;
+/// The LINEMODE suboption for setting the mode
+const LINEMODE_MODE: u8 = 1;
+/// The LINEMODE mode bit telling the client to do its own line editing
+const MODE_EDIT: u8 = 1;
+
+/// Builder for a [`TelnetInterface`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TelnetInterfaceBuilder<R, W> {
+    reader: R,
+    writer: W,
+    wrap_width: usize,
+}
+
+impl<R, W> TelnetInterfaceBuilder<R, W> {
+    pub fn new(reader: R, writer: W) -> Self {
+        TelnetInterfaceBuilder {
+            reader,
+            writer,
+            wrap_width: 0,
+        }
+    }
+
+    /// Set the width to which output is word-wrapped.  If 0 (the default),
+    /// output is wrapped to the window width reported by the client, if any.
+    pub fn wrap_width(mut self, width: usize) -> Self {
+        self.wrap_width = width;
+        self
+    }
+}
+
+impl<R: BufRead, W: Write> InterfaceBuilder for TelnetInterfaceBuilder<R, W> {
+    type Interface = TelnetInterface<R, W>;
+
+    fn build_interface(self) -> io::Result<Self::Interface> {
+        let mut iface = TelnetInterface {
+            reader: self.reader,
+            writer: self.writer,
+            parser: TelnetParser::default(),
+            lines: VecDeque::new(),
+            requested: HashSet::new(),
+            refused: HashSet::new(),
+            wrap_width: self.wrap_width,
+            window_width: None,
+            wrote_prompt: false,
+            wrote_last_output: false,
+        };
+        iface.request(DO, NAWS)?;
+        iface.request(DO, LINEMODE)?;
+        iface.writer.flush()?;
+        Ok(iface)
+    }
+}
+
+/// An [`Interface`] for clients speaking the Telnet protocol, such as `telnet`
+/// and MUD clients
+///
+/// The interface asks the client to report its window size (which is then
+/// used for word-wrapping) and to perform its own line editing, declines all
+/// other options, and strips Telnet commands from the input.  Line endings
+/// are converted to CR LF on output, and any of CR LF, CR NUL, or LF is
+/// accepted as a line ending on input.  Each prompt is followed by a Telnet
+/// "go ahead" so that clients can tell where it ends.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TelnetInterface<R, W> {
+    reader: R,
+    writer: W,
+    parser: TelnetParser,
+    // Complete lines of input that have not been returned yet
+    lines: VecDeque<String>,
+    // `(command, option)` pairs that we have sent
+    requested: HashSet<(u8, u8)>,
+    // Options that we have declined at the client's request
+    refused: HashSet<(u8, u8)>,
+    wrap_width: usize,
+    // The window width reported by the client
+    window_width: Option<u16>,
+    wrote_prompt: bool,
+    wrote_last_output: bool,
+}
+
+impl<R: BufRead, W: Write> TelnetInterface<R, W> {
+    /// Returns the width that output is currently wrapped to, or 0 if output
+    /// is not wrapped
+    pub fn width(&self) -> usize {
+        match (self.wrap_width, self.window_width) {
+            (0, Some(w)) => usize::from(w),
+            (w, _) => w,
+        }
+    }
+
+    fn request(&mut self, cmd: u8, opt: u8) -> io::Result<()> {
+        self.requested.insert((cmd, opt));
+        self.writer.write_all(&[IAC, cmd, opt])
+    }
+
+    // Write text with line endings converted to CR LF.  (UTF-8 text never
+    // contains the byte 255, so IAC never needs to be escaped.)
+    fn write_text(&mut self, text: &str) -> io::Result<()> {
+        self.writer.write_all(text.replace('\n', "\r\n").as_bytes())
+    }
+
+    fn handle(&mut self, event: TelnetEvent) -> io::Result<()> {
+        match event {
+            TelnetEvent::Line(line) => self.lines.push_back(line),
+            TelnetEvent::Negotiate(cmd, opt) => {
+                let reply = match cmd {
+                    WILL if opt == NAWS => Some(DO),
+                    WILL if opt == LINEMODE => {
+                        self.writer.write_all(&[
+                            IAC,
+                            SB,
+                            LINEMODE,
+                            LINEMODE_MODE,
+                            MODE_EDIT,
+                            IAC,
+                            SE,
+                        ])?;
+                        Some(DO)
+                    }
+                    WILL => Some(DONT),
+                    DO => Some(WONT),
+                    // Refusals and retractions need no response, as we
+                    // never enable anything other than what we request.
+                    _ => None,
+                };
+                if let Some(reply) = reply {
+                    // Only acknowledge each option once in order to avoid
+                    // negotiation loops.
+                    if !self.requested.contains(&(reply, opt)) && self.refused.insert((reply, opt))
+                    {
+                        self.writer.write_all(&[IAC, reply, opt])?;
+                    }
+                }
+                self.writer.flush()?;
+            }
+            TelnetEvent::Subnegotiation(data) => {
+                if let [NAWS, w1, w0, ..] = data[..] {
+                    let width = u16::from_be_bytes([w1, w0]);
+                    // A width of 0 means the client doesn't know.
+                    self.window_width = (width > 0).then_some(width);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<R: BufRead, W: Write> Interface for TelnetInterface<R, W> {
+    fn show_output(&mut self, text: &str) -> io::Result<()> {
+        if self.wrote_prompt {
+            self.write_text("\n")?;
+        }
+        if !text.is_empty() {
+            let text = wrap(text, self.width());
+            self.write_text(&text)?;
+            self.write_text("\n")?;
+            self.wrote_last_output = true;
+        } else {
+            self.wrote_last_output = false;
+        }
+        self.writer.flush()
+    }
+
+    fn get_input(&mut self) -> io::Result<Option<String>> {
+        if self.wrote_last_output {
+            self.write_text("\n")?;
+        }
+        self.write_text("> ")?;
+        self.writer.write_all(&[IAC, GA])?;
+        self.writer.flush()?;
+        self.wrote_prompt = true;
+        loop {
+            if let Some(line) = self.lines.pop_front() {
+                return Ok(Some(line));
+            }
+            let buf = self.reader.fill_buf()?;
+            if buf.is_empty() {
+                // End of input; any partial line is discarded.
+                self.write_text("\n")?;
+                return Ok(None);
+            }
+            let bytes = buf.to_vec();
+            self.reader.consume(bytes.len());
+            for b in bytes {
+                if let Some(event) = self.parser.feed(b) {
+                    self.handle(event)?;
+                }
+            }
+        }
+    }
+}
+
+/// Something of interest found in a stream of Telnet data
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum TelnetEvent {
+    /// A complete line of text, including the trailing newline
+    Line(String),
+    /// `IAC WILL/WONT/DO/DONT <option>`
+    Negotiate(u8, u8),
+    /// The data between `IAC SB` and `IAC SE`, starting with the option
+    Subnegotiation(Vec<u8>),
+}
+
+/// A byte-at-a-time parser for incoming Telnet data
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+struct TelnetParser {
+    state: ParserState,
+    line: Vec<u8>,
+    subneg: Vec<u8>,
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+enum ParserState {
+    #[default]
+    Data,
+    /// After a CR in the data
+    Cr,
+    /// After an IAC in the data
+    Iac,
+    /// After `IAC WILL/WONT/DO/DONT`
+    Negotiate(u8),
+    /// Within a subnegotiation
+    Sub,
+    /// After an IAC within a subnegotiation
+    SubIac,
+}
+
+impl TelnetParser {
+    fn feed(&mut self, b: u8) -> Option<TelnetEvent> {
+        match (self.state, b) {
+            (ParserState::Data | ParserState::Cr, IAC) => self.state = ParserState::Iac,
+            // CR LF, CR NUL, and bare LF all end a line.
+            (ParserState::Cr, b'\n' | b'\0') => self.state = ParserState::Data,
+            (ParserState::Data | ParserState::Cr, b'\r') => {
+                self.state = ParserState::Cr;
+                return Some(self.end_line());
+            }
+            (ParserState::Data, b'\n') => {
+                return Some(self.end_line());
+            }
+            // Backspace and delete, for clients that send each character as
+            // it's typed
+            (ParserState::Data | ParserState::Cr, 8 | 127) => {
+                self.state = ParserState::Data;
+                self.line.pop();
+            }
+            (ParserState::Data | ParserState::Cr, b) => {
+                self.state = ParserState::Data;
+                self.line.push(b);
+            }
+            (ParserState::Iac, IAC) => {
+                self.state = ParserState::Data;
+                self.line.push(IAC);
+            }
+            (ParserState::Iac, WILL | WONT | DO | DONT) => self.state = ParserState::Negotiate(b),
+            (ParserState::Iac, SB) => {
+                self.state = ParserState::Sub;
+                self.subneg.clear();
+            }
+            // Other commands (e.g., NOP, GA, "are you there") are ignored.
+            (ParserState::Iac, _) => self.state = ParserState::Data,
+            (ParserState::Negotiate(cmd), opt) => {
+                self.state = ParserState::Data;
+                return Some(TelnetEvent::Negotiate(cmd, opt));
+            }
+            (ParserState::Sub, IAC) => self.state = ParserState::SubIac,
+            (ParserState::Sub, b) => self.subneg.push(b),
+            (ParserState::SubIac, SE) => {
+                self.state = ParserState::Data;
+                return Some(TelnetEvent::Subnegotiation(std::mem::take(
+                    &mut self.subneg,
+                )));
+            }
+            (ParserState::SubIac, b) => {
+                // `IAC IAC` is an escaped 255; anything else is invalid, but
+                // we keep going.
+                self.state = ParserState::Sub;
+                self.subneg.push(b);
+            }
+        }
+        None
+    }
+
+    fn end_line(&mut self) -> TelnetEvent {
+        let mut line = String::from_utf8_lossy(&std::mem::take(&mut self.line)).into_owned();
+        line.push('\n');
+        TelnetEvent::Line(line)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(bytes: &[u8]) -> Vec<TelnetEvent> {
+        let mut parser = TelnetParser::default();
+        bytes.iter().filter_map(|&b| parser.feed(b)).collect()
+    }
+
+    #[test]
+    fn test_parse_lines() {
+        assert_eq!(
+            parse(b"look\r\nnorth\r\0east\nxyz"),
+            vec![
+                TelnetEvent::Line(String::from("look\n")),
+                TelnetEvent::Line(String::from("north\n")),
+                TelnetEvent::Line(String::from("east\n")),
+            ]
+        );
+        assert_eq!(
+            parse(b"loox\x08k\r\n\r\n"),
+            vec![
+                TelnetEvent::Line(String::from("look\n")),
+                TelnetEvent::Line(String::from("\n")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_commands() {
+        assert_eq!(
+            parse(&[
+                IAC, WILL, NAWS, b'g', IAC, SB, NAWS, 0, 80, 0, 24, IAC, SE, b'o', IAC, 246, b'\r',
+                b'\n',
+            ]),
+            vec![
+                TelnetEvent::Negotiate(WILL, NAWS),
+                TelnetEvent::Subnegotiation(vec![NAWS, 0, 80, 0, 24]),
+                TelnetEvent::Line(String::from("go\n")),
+            ]
+        );
+        assert_eq!(
+            parse(&[IAC, SB, NAWS, 1, IAC, IAC, 0, 24, IAC, SE]),
+            vec![TelnetEvent::Subnegotiation(vec![NAWS, 1, 255, 0, 24])]
+        );
+    }
+
+    #[test]
+    fn test_interface() {
+        let mut input = vec![IAC, WILL, NAWS, IAC, SB, NAWS, 0, 20, 0, 24, IAC, SE];
+        input.extend_from_slice(&[IAC, WILL, LINEMODE, IAC, DO, 1]);
+        input.extend_from_slice(b"look\r\n");
+        input.extend_from_slice(&[IAC, DO, 1]);
+        input.extend_from_slice(b"quit");
+        let mut iface = TelnetInterfaceBuilder::new(&input[..], Vec::new())
+            .build_interface()
+            .unwrap();
+        iface.show_output("Welcome.").unwrap();
+        assert_eq!(iface.get_input().unwrap().as_deref(), Some("look\n"));
+        assert_eq!(iface.width(), 20);
+        iface
+            .show_output("You are in the center room.  It is nice.")
+            .unwrap();
+        assert_eq!(iface.get_input().unwrap(), None);
+        let mut expected = vec![IAC, DO, NAWS, IAC, DO, LINEMODE];
+        expected.extend_from_slice(b"Welcome.\r\n\r\n> ");
+        expected.extend_from_slice(&[IAC, GA]);
+        expected.extend_from_slice(&[IAC, SB, LINEMODE, LINEMODE_MODE, MODE_EDIT, IAC, SE]);
+        // The client's DO for option 1 is refused only once.
+        expected.extend_from_slice(&[IAC, WONT, 1]);
+        expected.extend_from_slice(b"\r\nYou are in the\r\ncenter room.  It is\r\nnice.\r\n\r\n> ");
+        expected.extend_from_slice(&[IAC, GA]);
+        expected.extend_from_slice(b"\r\n");
+        assert_eq!(iface.writer, expected);
+    }
+}
@@ -0,0 +1,85 @@
+//! Games shared by the tests of the other modules
+
+use crate::{GameBuilder, GameEngine, Output, SavableEngine};
+use std::num::ParseIntError;
+
+/// A game that counts the commands entered
+///
+/// "stop" ends the game, "reset" asks whether to start counting again, and
+/// "save" asks for the game to be saved.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct Counter(pub(crate) u32);
+
+impl GameBuilder for Counter {
+    type Engine = Counter;
+
+    fn start(self) -> Output<Counter> {
+        Output::continue_with(self, "Count!")
+    }
+}
+
+impl GameEngine for Counter {
+    fn handle_input(self, input: &str) -> Output<Counter> {
+        match input.trim() {
+            "stop" => Output::goodbye(format!("Final count: {}", self.0)),
+            "reset" => Output::confirm(self, "Really reset?"),
+            "save" => Output::Save {
+                game: self,
+                messages: Vec::new(),
+            },
+            _ => Output::continue_with(Counter(self.0 + 1), (self.0 + 1).to_string()),
+        }
+    }
+
+    fn handle_answer(self, answer: bool) -> Output<Counter> {
+        let game = if answer { Counter(0) } else { self };
+        Output::continue_with(game, game.0.to_string())
+    }
+
+    fn location(&self) -> Option<String> {
+        Some(format!("Room {}", self.0))
+    }
+}
+
+impl SavableEngine for Counter {
+    type Error = ParseIntError;
+
+    fn save(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn restore(data: &str) -> Result<Counter, ParseIntError> {
+        data.parse().map(Counter)
+    }
+}
+
+/// A game that repeats back whatever is entered until "quit"
+///
+/// "save" asks for the game to be saved.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct Echo;
+
+impl GameBuilder for Echo {
+    type Engine = Echo;
+
+    fn start(self) -> Output<Echo> {
+        Output::continue_with(Echo, "Hello.")
+    }
+}
+
+impl GameEngine for Echo {
+    fn handle_input(self, input: &str) -> Output<Echo> {
+        match input.trim() {
+            "quit" => Output::goodbye("Bye."),
+            "save" => Output::Save {
+                game: Echo,
+                messages: Vec::new(),
+            },
+            s => Output::continue_with(Echo, format!("You said: {s}")),
+        }
+    }
+
+    fn location(&self) -> Option<String> {
+        Some(String::from("Echo Chamber"))
+    }
+}
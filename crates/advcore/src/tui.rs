@@ -0,0 +1,707 @@
+#[cfg(unix)]
+use crate::interface::Terminated;
+use crate::interface::{
+    Interface, InterfaceBuilder, OutputContext, ReadlineInterface, ReadlineInterfaceBuilder,
+};
+use crate::wrap::wrap;
+use crate::{Message, MessageKind, join_messages};
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{Attribute, Color, Print, ResetColor, SetAttribute, SetForegroundColor};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+use std::io::{self, ErrorKind, IsTerminal, Write};
+#[cfg(unix)]
+use std::sync::Arc;
+#[cfg(unix)]
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+/// The smallest terminal, in columns and rows, that the full-screen interface
+/// will use
+const MIN_SIZE: (u16, u16) = (20, 5);
+
+/// The most lines of output kept for scrolling back through
+const MAX_TRANSCRIPT: usize = 10_000;
+
+const PROMPT: &str = "> ";
+
+/// The prompt shown after Ctrl-C is pressed twice in a row
+const QUIT_PROMPT: &str = "Really quit? (y/n) ";
+
+/// How often to check for terminating signals while waiting for input
+const SIGNAL_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct TuiInterfaceBuilder {
+    color: bool,
+}
+
+impl TuiInterfaceBuilder {
+    pub fn new() -> Self {
+        TuiInterfaceBuilder::default()
+    }
+
+    /// Whether to highlight the prompt using color.  Defaults to false.
+    pub fn color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Returns whether the terminal can display the full-screen interface
+    pub fn is_supported() -> bool {
+        let dumb = std::env::var_os("TERM").is_none_or(|t| t.is_empty() || t == "dumb");
+        io::stdin().is_terminal()
+            && io::stdout().is_terminal()
+            && (cfg!(windows) || !dumb)
+            && terminal::size().is_ok_and(|(w, h)| w >= MIN_SIZE.0 && h >= MIN_SIZE.1)
+    }
+}
+
+impl InterfaceBuilder for TuiInterfaceBuilder {
+    type Interface = TuiInterface;
+
+    fn build_interface(self) -> io::Result<Self::Interface> {
+        if TuiInterfaceBuilder::is_supported() {
+            if let Ok(screen) = Screen::new(self.color) {
+                return Ok(TuiInterface(Inner::FullScreen(screen)));
+            }
+        }
+        ReadlineInterfaceBuilder::new()
+            .color(self.color)
+            .build_interface()
+            .map(|rl| TuiInterface(Inner::Readline(rl)))
+    }
+}
+
+/// A full-screen terminal interface with a scrollback pane, a status line
+/// showing the player's location, [status](crate::GameEngine::status), and
+/// turn count, and an input line with history
+///
+/// The pane can be scrolled with Page Up and Page Down.  Pressing Ctrl-C
+/// discards the line being edited; pressing it again asks the player whether
+/// they really want to quit.  On Unix, SIGTERM and SIGHUP cause
+/// [`Interface::get_input()`] to return a [`Terminated`](crate::Terminated)
+/// error, and the terminal is restored when the interface is dropped.  If the
+/// terminal can't support the full-screen interface (e.g., because it's too
+/// small or stdin is not a terminal), a [`ReadlineInterface`] is used instead.
+#[derive(Debug)]
+pub struct TuiInterface(Inner);
+
+#[derive(Debug)]
+enum Inner {
+    FullScreen(Screen),
+    Readline(ReadlineInterface),
+}
+
+impl TuiInterface {
+    /// Returns whether the full-screen interface is in use, as opposed to the
+    /// readline fallback
+    pub fn is_full_screen(&self) -> bool {
+        matches!(self.0, Inner::FullScreen(_))
+    }
+}
+
+impl Interface for TuiInterface {
+    fn show_output(&mut self, text: &str) -> io::Result<()> {
+        self.show_output_with_context(text, &OutputContext::default())
+    }
+
+    fn show_output_with_context(&mut self, text: &str, ctx: &OutputContext) -> io::Result<()> {
+        match &mut self.0 {
+            Inner::FullScreen(screen) => screen.show_messages(&[Message::narrative(text)], ctx),
+            Inner::Readline(rl) => rl.show_output_with_context(text, ctx),
+        }
+    }
+
+    /// If color is enabled, the full-screen interface shows parser errors in
+    /// yellow, system messages in cyan, and status messages in bold.
+    fn show_messages(&mut self, messages: &[Message], ctx: &OutputContext) -> io::Result<()> {
+        match &mut self.0 {
+            Inner::FullScreen(screen) => screen.show_messages(messages, ctx),
+            Inner::Readline(rl) => rl.show_messages(messages, ctx),
+        }
+    }
+
+    fn get_input(&mut self) -> io::Result<Option<String>> {
+        match &mut self.0 {
+            Inner::FullScreen(screen) => screen.get_input(),
+            Inner::Readline(rl) => rl.get_input(),
+        }
+    }
+
+    /// The full-screen interface rewraps its output whenever the terminal is
+    /// resized, so it does not report its width.
+    fn columns(&mut self) -> Option<usize> {
+        match &mut self.0 {
+            Inner::FullScreen(_) => None,
+            Inner::Readline(rl) => rl.columns(),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Screen {
+    stdout: io::Stdout,
+    width: u16,
+    height: u16,
+    color: bool,
+    transcript: Vec<Line>,
+    // How many rows the pane is scrolled back from the end of the transcript
+    scroll: usize,
+    location: String,
+    progress: String,
+    editor: LineEditor,
+    // Whether Ctrl-C was pressed since the last line of input
+    interrupted: bool,
+    // Whether the player is being asked whether they really want to quit
+    confirming_quit: bool,
+    // The game's final output, to print once the screen is restored
+    farewell: Option<String>,
+    // Set when the process receives SIGTERM or SIGHUP
+    #[cfg(unix)]
+    terminated: Arc<AtomicBool>,
+    #[cfg(unix)]
+    signal_ids: Vec<signal_hook::SigId>,
+}
+
+impl Screen {
+    fn new(color: bool) -> io::Result<Screen> {
+        let (width, height) = terminal::size()?;
+        // Catching the signals keeps them from killing the process with the
+        // terminal still in raw mode and on the alternate screen.
+        #[cfg(unix)]
+        let terminated = Arc::new(AtomicBool::new(false));
+        #[cfg(unix)]
+        let signal_ids = [signal_hook::consts::SIGTERM, signal_hook::consts::SIGHUP]
+            .into_iter()
+            .map(|sig| signal_hook::flag::register(sig, Arc::clone(&terminated)))
+            .collect::<io::Result<Vec<_>>>()?;
+        let mut stdout = io::stdout();
+        let r = terminal::enable_raw_mode().and_then(|()| {
+            execute!(stdout, EnterAlternateScreen).inspect_err(|_| {
+                let _ = terminal::disable_raw_mode();
+            })
+        });
+        if let Err(e) = r {
+            #[cfg(unix)]
+            unregister(&signal_ids);
+            return Err(e);
+        }
+        Ok(Screen {
+            stdout,
+            width,
+            height,
+            color,
+            transcript: Vec::new(),
+            scroll: 0,
+            location: String::new(),
+            progress: String::new(),
+            editor: LineEditor::default(),
+            interrupted: false,
+            confirming_quit: false,
+            farewell: None,
+            #[cfg(unix)]
+            terminated,
+            #[cfg(unix)]
+            signal_ids,
+        })
+    }
+
+    fn show_messages(&mut self, messages: &[Message], ctx: &OutputContext) -> io::Result<()> {
+        let text = join_messages(messages);
+        if !text.is_empty() {
+            if !self.transcript.is_empty() {
+                self.transcript.push(Line::default());
+            }
+            for m in messages {
+                self.transcript.extend(m.text.lines().map(|ln| Line {
+                    kind: Some(m.kind),
+                    text: ln.to_owned(),
+                }));
+            }
+            let excess = self.transcript.len().saturating_sub(MAX_TRANSCRIPT);
+            self.transcript.drain(..excess);
+        }
+        self.location = ctx.location.clone().unwrap_or_default();
+        self.progress = match &ctx.status {
+            Some(status) => format!("{status}  Turns: {}", ctx.turn),
+            None => format!("Turns: {}", ctx.turn),
+        };
+        self.scroll = 0;
+        if ctx.ended {
+            self.farewell = Some(text);
+        }
+        self.draw()
+    }
+
+    fn get_input(&mut self) -> io::Result<Option<String>> {
+        loop {
+            self.draw()?;
+            match self.next_event()? {
+                Event::Key(key) if key.kind != KeyEventKind::Release && self.confirming_quit => {
+                    // As with `ReadlineInterface`, another Ctrl-C or end of
+                    // input counts as a yes.
+                    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+                    match key.code {
+                        KeyCode::Char('y' | 'Y') if !ctrl => return Ok(None),
+                        KeyCode::Char('c' | 'd') if ctrl => return Ok(None),
+                        _ => {
+                            self.confirming_quit = false;
+                            self.interrupted = false;
+                        }
+                    }
+                }
+                Event::Key(key) if key.kind != KeyEventKind::Release => match key.code {
+                    KeyCode::PageUp => {
+                        let max = self
+                            .rows(usize::MAX)
+                            .len()
+                            .saturating_sub(self.pane_height());
+                        self.scroll = (self.scroll + self.page()).min(max);
+                    }
+                    KeyCode::PageDown => self.scroll = self.scroll.saturating_sub(self.page()),
+                    _ => match self.editor.handle_key(key) {
+                        Edit::Continue => (),
+                        Edit::Interrupt => {
+                            self.confirming_quit = std::mem::replace(&mut self.interrupted, true);
+                        }
+                        Edit::Submit(line) => {
+                            self.interrupted = false;
+                            if !self.transcript.is_empty() {
+                                self.transcript.push(Line::default());
+                            }
+                            self.transcript.push(Line {
+                                kind: None,
+                                text: format!("{PROMPT}{line}"),
+                            });
+                            self.scroll = 0;
+                            return Ok(Some(line));
+                        }
+                        Edit::Eof => return Ok(None),
+                    },
+                },
+                Event::Resize(width, height) => {
+                    self.width = width;
+                    self.height = height;
+                }
+                _ => (),
+            }
+        }
+    }
+
+    // Wait for the next terminal event, checking periodically for
+    // terminating signals
+    fn next_event(&self) -> io::Result<Event> {
+        loop {
+            #[cfg(unix)]
+            if self.terminated.load(Ordering::SeqCst) {
+                return Err(Terminated.into());
+            }
+            match event::poll(SIGNAL_CHECK_INTERVAL) {
+                Ok(true) => return event::read(),
+                Ok(false) => (),
+                Err(e) if e.kind() == ErrorKind::Interrupted => (),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    fn pane_height(&self) -> usize {
+        usize::from(self.height.saturating_sub(2))
+    }
+
+    fn page(&self) -> usize {
+        self.pane_height().saturating_sub(1).max(1)
+    }
+
+    // Returns the last `limit` rows of the transcript as laid out on screen
+    fn rows(&self, limit: usize) -> Vec<Line> {
+        transcript_rows(&self.transcript, usize::from(self.width), limit)
+    }
+
+    fn draw(&mut self) -> io::Result<()> {
+        let width = usize::from(self.width);
+        let pane_height = self.pane_height();
+        let rows = self.rows(pane_height.saturating_add(self.scroll));
+        let end = rows.len() - self.scroll.min(rows.len());
+        let start = end.saturating_sub(pane_height);
+        queue!(
+            self.stdout,
+            Hide,
+            MoveTo(0, 0),
+            SetAttribute(Attribute::Reverse),
+            Print(status_line(&self.location, &self.progress, width)),
+            SetAttribute(Attribute::Reset),
+        )?;
+        for i in 0..pane_height {
+            queue!(
+                self.stdout,
+                MoveTo(0, u16::try_from(i + 1).unwrap_or(u16::MAX))
+            )?;
+            if let Some(row) = rows.get(start + i) {
+                let style = if self.color { row.kind } else { None };
+                match style {
+                    Some(MessageKind::ParserError) => {
+                        queue!(self.stdout, SetForegroundColor(Color::Yellow))?;
+                    }
+                    Some(MessageKind::System) => {
+                        queue!(self.stdout, SetForegroundColor(Color::Cyan))?;
+                    }
+                    Some(MessageKind::Status) => {
+                        queue!(self.stdout, SetAttribute(Attribute::Bold))?;
+                    }
+                    Some(MessageKind::Narrative) | None => (),
+                }
+                queue!(
+                    self.stdout,
+                    Print(&row.text),
+                    SetAttribute(Attribute::Reset),
+                    ResetColor
+                )?;
+            }
+            queue!(self.stdout, Clear(ClearType::UntilNewLine))?;
+        }
+        let (prompt, (visible, cursor)) = if self.confirming_quit {
+            (QUIT_PROMPT, (String::new(), 0))
+        } else {
+            (PROMPT, self.editor.view(width.saturating_sub(PROMPT.len())))
+        };
+        queue!(self.stdout, MoveTo(0, self.height.saturating_sub(1)))?;
+        if self.color {
+            queue!(
+                self.stdout,
+                SetForegroundColor(Color::Green),
+                SetAttribute(Attribute::Bold),
+                Print(prompt),
+                SetAttribute(Attribute::Reset),
+                ResetColor,
+            )?;
+        } else {
+            queue!(self.stdout, Print(prompt))?;
+        }
+        let cursor = u16::try_from(prompt.len() + cursor).unwrap_or(u16::MAX);
+        queue!(
+            self.stdout,
+            Print(visible),
+            Clear(ClearType::UntilNewLine),
+            MoveTo(cursor, self.height.saturating_sub(1)),
+            Show,
+        )?;
+        self.stdout.flush()
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        // There's nothing else to be done if restoring the terminal fails.
+        let _ = execute!(self.stdout, LeaveAlternateScreen, Show);
+        let _ = terminal::disable_raw_mode();
+        #[cfg(unix)]
+        unregister(&self.signal_ids);
+        if let Some(text) = self.farewell.take().filter(|s| !s.is_empty()) {
+            let _ = writeln!(self.stdout, "{text}");
+        }
+    }
+}
+
+// Remove the signal handlers installed by `Screen::new()`
+#[cfg(unix)]
+fn unregister(ids: &[signal_hook::SigId]) {
+    for &id in ids {
+        signal_hook::low_level::unregister(id);
+    }
+}
+
+// A line of the transcript
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+struct Line {
+    // The kind of message the line is part of, or `None` for input and
+    // blank lines between messages
+    kind: Option<MessageKind>,
+    text: String,
+}
+
+// Lay out the last `limit` rows of `lines` in a pane `width` columns wide,
+// word-wrapping where possible and breaking long words where not
+fn transcript_rows(lines: &[Line], width: usize, limit: usize) -> Vec<Line> {
+    let width = width.max(1);
+    let mut rows = Vec::new();
+    for line in lines.iter().rev() {
+        if rows.len() >= limit {
+            break;
+        }
+        let mut line_rows = Vec::new();
+        for row in wrap(&line.text, width).split('\n') {
+            let chars = row.chars().collect::<Vec<_>>();
+            if chars.is_empty() {
+                line_rows.push(String::new());
+            }
+            line_rows.extend(chars.chunks(width).map(|c| c.iter().collect::<String>()));
+        }
+        let line_rows = line_rows.into_iter().map(|text| Line {
+            kind: line.kind,
+            text,
+        });
+        rows.extend(line_rows.rev());
+    }
+    rows.truncate(limit);
+    rows.reverse();
+    rows
+}
+
+// Lay out a status line `width` columns wide with `left` and `right` at
+// either end, truncating `left` if there isn't room for both
+fn status_line(left: &str, right: &str, width: usize) -> String {
+    let right_len = right.chars().count();
+    let room = width.saturating_sub(right_len + 3);
+    let left = left.chars().take(room).collect::<String>();
+    let gap = width.saturating_sub(left.chars().count() + right_len + 2);
+    let mut line = format!(" {left}{:gap$}{right} ", "");
+    if line.chars().count() > width {
+        line = line.chars().take(width).collect();
+    }
+    line
+}
+
+// The result of passing a key press to a `LineEditor`
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Edit {
+    Continue,
+    Submit(String),
+    /// Ctrl-C was pressed, discarding the line
+    Interrupt,
+    Eof,
+}
+
+// A single-line text editor with history
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+struct LineEditor {
+    buffer: Vec<char>,
+    cursor: usize,
+    history: Vec<String>,
+    // The index into `history` of the entry being edited; equal to
+    // `history.len()` when editing a new line
+    history_pos: usize,
+    // The new line being edited while browsing history
+    draft: Vec<char>,
+}
+
+impl LineEditor {
+    fn handle_key(&mut self, key: KeyEvent) -> Edit {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Enter => {
+                let line = self.buffer.drain(..).collect::<String>();
+                self.cursor = 0;
+                if !line.trim().is_empty() && self.history.last() != Some(&line) {
+                    self.history.push(line.clone());
+                }
+                self.history_pos = self.history.len();
+                self.draft.clear();
+                return Edit::Submit(line);
+            }
+            KeyCode::Char('c') if ctrl => {
+                self.buffer.clear();
+                self.cursor = 0;
+                self.history_pos = self.history.len();
+                self.draft.clear();
+                return Edit::Interrupt;
+            }
+            KeyCode::Char('d') if ctrl && self.buffer.is_empty() => return Edit::Eof,
+            KeyCode::Char('d') if ctrl => self.delete(),
+            KeyCode::Char('a') if ctrl => self.cursor = 0,
+            KeyCode::Char('e') if ctrl => self.cursor = self.buffer.len(),
+            KeyCode::Char('b') if ctrl => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Char('f') if ctrl => self.cursor = (self.cursor + 1).min(self.buffer.len()),
+            KeyCode::Char('u') if ctrl => {
+                self.buffer.drain(..self.cursor);
+                self.cursor = 0;
+            }
+            KeyCode::Char('k') if ctrl => self.buffer.truncate(self.cursor),
+            KeyCode::Char('p') if ctrl => self.history_back(),
+            KeyCode::Char('n') if ctrl => self.history_forward(),
+            KeyCode::Char(c) if !ctrl => {
+                self.buffer.insert(self.cursor, c);
+                self.cursor += 1;
+            }
+            KeyCode::Backspace if self.cursor > 0 => {
+                self.cursor -= 1;
+                self.buffer.remove(self.cursor);
+            }
+            KeyCode::Delete => self.delete(),
+            KeyCode::Left => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Right => self.cursor = (self.cursor + 1).min(self.buffer.len()),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => self.cursor = self.buffer.len(),
+            KeyCode::Up => self.history_back(),
+            KeyCode::Down => self.history_forward(),
+            _ => (),
+        }
+        Edit::Continue
+    }
+
+    fn delete(&mut self) {
+        if self.cursor < self.buffer.len() {
+            self.buffer.remove(self.cursor);
+        }
+    }
+
+    fn history_back(&mut self) {
+        if self.history_pos == 0 {
+            return;
+        }
+        if self.history_pos == self.history.len() {
+            self.draft = std::mem::take(&mut self.buffer);
+        }
+        self.history_pos -= 1;
+        self.buffer = self.history[self.history_pos].chars().collect();
+        self.cursor = self.buffer.len();
+    }
+
+    fn history_forward(&mut self) {
+        if self.history_pos >= self.history.len() {
+            return;
+        }
+        self.history_pos += 1;
+        self.buffer = match self.history.get(self.history_pos) {
+            Some(line) => line.chars().collect(),
+            None => std::mem::take(&mut self.draft),
+        };
+        self.cursor = self.buffer.len();
+    }
+
+    // Returns the part of the buffer to show in a field `width` columns wide,
+    // scrolled so that the cursor is visible, along with the cursor's column
+    // within the field
+    fn view(&self, width: usize) -> (String, usize) {
+        let width = width.max(1);
+        let start = (self.cursor + 1).saturating_sub(width);
+        let visible = self.buffer.iter().skip(start).take(width).collect();
+        (visible, self.cursor - start)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(editor: &mut LineEditor, code: KeyCode) -> Edit {
+        editor.handle_key(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    fn ctrl(editor: &mut LineEditor, c: char) -> Edit {
+        editor.handle_key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL))
+    }
+
+    fn type_str(editor: &mut LineEditor, s: &str) {
+        for c in s.chars() {
+            press(editor, KeyCode::Char(c));
+        }
+    }
+
+    #[test]
+    fn test_line_editor() {
+        let mut editor = LineEditor::default();
+        type_str(&mut editor, "lok");
+        press(&mut editor, KeyCode::Left);
+        type_str(&mut editor, "o");
+        assert_eq!(
+            press(&mut editor, KeyCode::Enter),
+            Edit::Submit("look".into())
+        );
+        type_str(&mut editor, "go nrth");
+        press(&mut editor, KeyCode::Home);
+        ctrl(&mut editor, 'k');
+        type_str(&mut editor, "north");
+        assert_eq!(
+            press(&mut editor, KeyCode::Enter),
+            Edit::Submit("north".into())
+        );
+        type_str(&mut editor, "dr");
+        press(&mut editor, KeyCode::Up);
+        assert_eq!(editor.view(80), (String::from("north"), 5));
+        press(&mut editor, KeyCode::Up);
+        press(&mut editor, KeyCode::Up);
+        assert_eq!(editor.view(80), (String::from("look"), 4));
+        press(&mut editor, KeyCode::Down);
+        press(&mut editor, KeyCode::Down);
+        assert_eq!(editor.view(80), (String::from("dr"), 2));
+        press(&mut editor, KeyCode::Backspace);
+        assert_eq!(ctrl(&mut editor, 'd'), Edit::Continue);
+        press(&mut editor, KeyCode::Backspace);
+        assert_eq!(ctrl(&mut editor, 'd'), Edit::Eof);
+        assert_eq!(editor.history, ["look", "north"]);
+    }
+
+    #[test]
+    fn test_line_editor_interrupt() {
+        let mut editor = LineEditor::default();
+        type_str(&mut editor, "look");
+        press(&mut editor, KeyCode::Enter);
+        type_str(&mut editor, "go");
+        press(&mut editor, KeyCode::Up);
+        assert_eq!(ctrl(&mut editor, 'c'), Edit::Interrupt);
+        assert_eq!(editor.view(80), (String::new(), 0));
+        press(&mut editor, KeyCode::Down);
+        assert_eq!(editor.view(80), (String::new(), 0));
+        press(&mut editor, KeyCode::Up);
+        assert_eq!(editor.view(80), (String::from("look"), 4));
+    }
+
+    #[test]
+    fn test_line_editor_view() {
+        let mut editor = LineEditor::default();
+        type_str(&mut editor, "abcdefgh");
+        assert_eq!(editor.view(5), (String::from("efgh"), 4));
+        press(&mut editor, KeyCode::Home);
+        assert_eq!(editor.view(5), (String::from("abcde"), 0));
+    }
+
+    #[test]
+    fn test_transcript_rows() {
+        let lines = [
+            Line {
+                kind: Some(MessageKind::Narrative),
+                text: String::from("The quick brown fox jumps over the lazy dog."),
+            },
+            Line::default(),
+            Line {
+                kind: Some(MessageKind::ParserError),
+                text: String::from("Supercalifragilistic"),
+            },
+        ];
+        let rows = transcript_rows(&lines, 12, usize::MAX);
+        assert_eq!(
+            rows.iter().map(|r| r.text.as_str()).collect::<Vec<_>>(),
+            [
+                "The quick",
+                "brown fox",
+                "jumps over",
+                "the lazy",
+                "dog.",
+                "",
+                "Supercalifra",
+                "gilistic",
+            ]
+        );
+        assert_eq!(rows[4].kind, Some(MessageKind::Narrative));
+        assert_eq!(rows[5].kind, None);
+        assert_eq!(rows[7].kind, Some(MessageKind::ParserError));
+        let rows = transcript_rows(&lines, 12, 3);
+        assert_eq!(
+            rows.iter().map(|r| r.text.as_str()).collect::<Vec<_>>(),
+            ["", "Supercalifra", "gilistic"]
+        );
+    }
+
+    #[test]
+    fn test_status_line() {
+        assert_eq!(status_line("Hall", "Turns: 3", 20), " Hall      Turns: 3 ");
+        assert_eq!(
+            status_line("Grand Ballroom", "Turns: 3", 20),
+            " Grand Bal Turns: 3 "
+        );
+        assert_eq!(status_line("Hall", "Turns: 1000", 8), " Turns: ");
+    }
+}
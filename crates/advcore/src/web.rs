@@ -0,0 +1,463 @@
+//! Playing games in a web browser; see [`Server::serve_web()`]
+//!
+//! The server speaks just enough HTTP/1.1 to serve a single page and accept
+//! WebSocket connections.  Messages over the WebSocket use the same JSON
+//! objects as [`JsonLinesInterface`]: the server sends `"output"` and
+//! `"error"` events, and the page sends `{"command": ...}` objects.
+//!
+//! [`Server::serve_web()`]: crate::Server::serve_web
+//! [`JsonLinesInterface`]: crate::JsonLinesInterface
+
+use crate::interface::{Interface, InterfaceBuilder, OutputContext};
+use crate::jsonl::{error_event, output_event, parse_command, text_messages};
+use crate::wrap::WrapInterfaceBuilder;
+use crate::{GameBuilder, play_game};
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, ErrorKind, Read, Write};
+use std::net::{IpAddr, TcpStream};
+use tungstenite::handshake::derive_accept_key;
+use tungstenite::protocol::Role;
+use tungstenite::{Message, WebSocket};
+
+/// The page served at `/`
+const PAGE: &str = include_str!("web.html");
+
+/// The maximum size of an HTTP request's head
+const MAX_REQUEST_SIZE: u64 = 8192;
+
+/// Respond to a single HTTP request on `stream`, running a game from `game`
+/// if it's a WebSocket request
+pub(crate) fn handle_connection<G: GameBuilder + Clone>(
+    stream: TcpStream,
+    game: G,
+    wrap_width: usize,
+) -> io::Result<()> {
+    let mut reader = BufReader::new(stream);
+    let Some(req) = Request::read(&mut reader)? else {
+        return Ok(());
+    };
+    // The client can't send any WebSocket frames until it receives our
+    // response, but pass along anything that's been buffered anyway.
+    let buffered = reader.buffer().to_vec();
+    let mut stream = reader.into_inner();
+    if !req.is_trusted_host(stream.local_addr()?.ip()) {
+        return respond(&mut stream, "403 Forbidden", "text/plain", "");
+    }
+    if req.method != "GET" {
+        return respond(&mut stream, "405 Method Not Allowed", "text/plain", "");
+    }
+    match req.path.as_str() {
+        "/" | "/index.html" => respond(&mut stream, "200 OK", "text/html; charset=utf-8", PAGE),
+        "/ws" => {
+            let Some(key) = req.websocket_key() else {
+                return respond(
+                    &mut stream,
+                    "400 Bad Request",
+                    "text/plain",
+                    "Expected a WebSocket request\n",
+                );
+            };
+            if !req.is_same_origin() {
+                return respond(&mut stream, "403 Forbidden", "text/plain", "");
+            }
+            write!(
+                stream,
+                concat!(
+                    "HTTP/1.1 101 Switching Protocols\r\n",
+                    "Upgrade: websocket\r\n",
+                    "Connection: Upgrade\r\n",
+                    "Sec-WebSocket-Accept: {}\r\n",
+                    "\r\n",
+                ),
+                derive_accept_key(key.as_bytes())
+            )?;
+            let socket = WebSocket::from_partially_read(stream, buffered, Role::Server, None);
+            let iface = WrapInterfaceBuilder::new(WebSocketInterfaceBuilder(socket), wrap_width);
+            play_game(iface, game, false)
+        }
+        _ => respond(&mut stream, "404 Not Found", "text/plain", "Not found\n"),
+    }
+}
+
+fn respond<W: Write>(
+    writer: &mut W,
+    status: &str,
+    content_type: &str,
+    body: &str,
+) -> io::Result<()> {
+    write!(
+        writer,
+        concat!(
+            "HTTP/1.1 {}\r\n",
+            "Content-Type: {}\r\n",
+            "Content-Length: {}\r\n",
+            "Cache-Control: no-store\r\n",
+            "Connection: close\r\n",
+            "\r\n",
+            "{}",
+        ),
+        status,
+        content_type,
+        body.len(),
+        body
+    )?;
+    writer.flush()
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Request {
+    method: String,
+    path: String,
+    // Keyed by lowercased name
+    headers: HashMap<String, String>,
+}
+
+impl Request {
+    // Read the request line and headers of an HTTP request.  Returns `None`
+    // if the client disconnects or sends something that isn't HTTP.
+    fn read<R: BufRead>(reader: &mut R) -> io::Result<Option<Request>> {
+        let mut head = reader.take(MAX_REQUEST_SIZE);
+        let mut line = String::new();
+        head.read_line(&mut line)?;
+        let mut words = line.split_whitespace();
+        let (Some(method), Some(path), Some(_version)) = (words.next(), words.next(), words.next())
+        else {
+            return Ok(None);
+        };
+        let mut req = Request {
+            method: method.to_owned(),
+            path: path.to_owned(),
+            headers: HashMap::new(),
+        };
+        loop {
+            line.clear();
+            if head.read_line(&mut line)? == 0 {
+                // The client disconnected or the head was too long.
+                return Ok(None);
+            }
+            let line = line.trim_end_matches(['\r', '\n']);
+            if line.is_empty() {
+                return Ok(Some(req));
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                req.headers
+                    .insert(name.trim().to_ascii_lowercase(), value.trim().to_owned());
+            }
+        }
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).map(String::as_str)
+    }
+
+    // Returns the client's key if this is a valid WebSocket handshake
+    fn websocket_key(&self) -> Option<&str> {
+        let upgrade = self
+            .header("upgrade")
+            .is_some_and(|v| v.eq_ignore_ascii_case("websocket"));
+        let connection = self.header("connection").is_some_and(|v| {
+            v.split(',')
+                .any(|s| s.trim().eq_ignore_ascii_case("upgrade"))
+        });
+        let version = self.header("sec-websocket-version") == Some("13");
+        if upgrade && connection && version {
+            self.header("sec-websocket-key")
+        } else {
+            None
+        }
+    }
+
+    // Browsers send the origin of the page making a WebSocket request; reject
+    // requests from pages on other sites so that they can't play games
+    // behind the user's back.  Non-browser clients may omit the header.
+    fn is_same_origin(&self) -> bool {
+        match (self.header("origin"), self.header("host")) {
+            (None, _) => true,
+            (Some(origin), Some(host)) => origin
+                .strip_prefix("http://")
+                .or_else(|| origin.strip_prefix("https://"))
+                .is_some_and(|o| o.eq_ignore_ascii_case(host)),
+            (Some(_), None) => false,
+        }
+    }
+
+    // Returns true if the Host header names the server as `localhost` or by
+    // the address that the request arrived on.  This stops DNS rebinding, in
+    // which another site points its domain name at this server so that its
+    // pages pass as same-origin.
+    fn is_trusted_host(&self, local: IpAddr) -> bool {
+        let Some(name) = self.header("host").and_then(strip_port) else {
+            return false;
+        };
+        name.eq_ignore_ascii_case("localhost")
+            || name
+                .parse::<IpAddr>()
+                .is_ok_and(|ip| ip.is_loopback() || ip.to_canonical() == local.to_canonical())
+    }
+}
+
+// Remove the optional port from the value of a Host header, along with the
+// brackets around an IPv6 address.  Returns `None` if the port is invalid.
+fn strip_port(host: &str) -> Option<&str> {
+    let (name, port) = match host.strip_prefix('[') {
+        Some(rest) => {
+            let (name, port) = rest.split_once(']')?;
+            if port.is_empty() {
+                return Some(name);
+            }
+            (name, port.strip_prefix(':')?)
+        }
+        None => match host.rsplit_once(':') {
+            Some(pair) => pair,
+            None => return Some(host),
+        },
+    };
+    port.parse::<u16>().is_ok().then_some(name)
+}
+
+#[derive(Debug)]
+struct WebSocketInterfaceBuilder<S>(WebSocket<S>);
+
+impl<S: Read + Write> InterfaceBuilder for WebSocketInterfaceBuilder<S> {
+    type Interface = WebSocketInterface<S>;
+
+    fn build_interface(self) -> io::Result<Self::Interface> {
+        Ok(WebSocketInterface(self.0))
+    }
+}
+
+// An interface that exchanges JSON events over a WebSocket, closing it when
+// the game is over
+#[derive(Debug)]
+struct WebSocketInterface<S: Read + Write>(WebSocket<S>);
+
+impl<S: Read + Write> WebSocketInterface<S> {
+    fn emit(&mut self, event: &serde_json::Value) -> io::Result<()> {
+        self.0
+            .send(Message::text(event.to_string()))
+            .map_err(into_io_error)
+    }
+}
+
+impl<S: Read + Write> Interface for WebSocketInterface<S> {
+    fn show_output(&mut self, text: &str) -> io::Result<()> {
+        self.show_output_with_context(text, &OutputContext::default())
+    }
+
+    fn show_output_with_context(&mut self, text: &str, ctx: &OutputContext) -> io::Result<()> {
+        self.show_messages(&text_messages(text), ctx)
+    }
+
+    fn show_messages(
+        &mut self,
+        messages: &[crate::Message],
+        ctx: &OutputContext,
+    ) -> io::Result<()> {
+        self.emit(&output_event(messages, ctx))
+    }
+
+    fn get_input(&mut self) -> io::Result<Option<String>> {
+        loop {
+            let text = match self.0.read() {
+                Ok(Message::Text(text)) => text,
+                Ok(Message::Binary(_)) => {
+                    self.emit(&error_event("expected a text message"))?;
+                    continue;
+                }
+                // Pings are answered automatically.
+                Ok(Message::Ping(_) | Message::Pong(_) | Message::Frame(_)) => continue,
+                Ok(Message::Close(_)) => return Ok(None),
+                Err(tungstenite::Error::Io(e))
+                    if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) =>
+                {
+                    let ctx = OutputContext {
+                        ended: true,
+                        ..OutputContext::default()
+                    };
+                    self.show_output_with_context(
+                        "You have been idle for too long.  Goodbye.",
+                        &ctx,
+                    )?;
+                    return Ok(None);
+                }
+                Err(tungstenite::Error::ConnectionClosed | tungstenite::Error::AlreadyClosed) => {
+                    return Ok(None);
+                }
+                Err(e) => return Err(into_io_error(e)),
+            };
+            if text.trim().is_empty() {
+                continue;
+            }
+            match parse_command(&text) {
+                Ok(command) => return Ok(Some(command)),
+                Err(message) => self.emit(&error_event(&message))?,
+            }
+        }
+    }
+}
+
+impl<S: Read + Write> Drop for WebSocketInterface<S> {
+    fn drop(&mut self) {
+        // The client may already be gone, in which case there's no one to
+        // tell.
+        if self.0.close(None).is_ok() {
+            let _ = self.0.flush();
+        }
+    }
+}
+
+fn into_io_error(e: tungstenite::Error) -> io::Error {
+    match e {
+        tungstenite::Error::Io(e) => e,
+        e => io::Error::other(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Server;
+    use crate::testutil::Echo;
+    use serde_json::{Value, json};
+    use std::net::{SocketAddr, TcpListener};
+
+    fn start_server() -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || Server::new().serve_web(&listener, Echo));
+        addr
+    }
+
+    fn get(addr: SocketAddr, request: &str) -> String {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(request.as_bytes()).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    fn read_event(socket: &mut WebSocket<TcpStream>) -> Value {
+        match socket.read().unwrap() {
+            Message::Text(text) => serde_json::from_str(&text).unwrap(),
+            msg => panic!("unexpected message: {msg:?}"),
+        }
+    }
+
+    #[test]
+    fn test_page() {
+        let addr = start_server();
+        let response = get(addr, "GET / HTTP/1.1\r\nHost: localhost\r\n\r\n");
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains("Content-Type: text/html; charset=utf-8\r\n"));
+        assert!(response.ends_with(PAGE));
+        let response = get(addr, "GET /missing HTTP/1.1\r\nHost: localhost\r\n\r\n");
+        assert!(response.starts_with("HTTP/1.1 404 Not Found\r\n"));
+        let response = get(addr, "POST / HTTP/1.1\r\nHost: localhost\r\n\r\n");
+        assert!(response.starts_with("HTTP/1.1 405 Method Not Allowed\r\n"));
+        let response = get(addr, "GET /ws HTTP/1.1\r\nHost: localhost\r\n\r\n");
+        assert!(response.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+    }
+
+    #[test]
+    fn test_websocket() {
+        let addr = start_server();
+        let stream = TcpStream::connect(addr).unwrap();
+        let (mut socket, _) = tungstenite::client(format!("ws://{addr}/ws"), stream).unwrap();
+        assert_eq!(
+            read_event(&mut socket),
+            json!({
+                "version": 1,
+                "type": "output",
+                "text": "Hello.",
+                "messages": [{"kind": "narrative", "text": "Hello."}],
+                "ended": false,
+                "location": "Echo Chamber",
+                "status": null,
+                "turn": 0,
+            })
+        );
+        socket.send(Message::text("look")).unwrap();
+        assert_eq!(read_event(&mut socket)["type"], "error");
+        socket
+            .send(Message::text(r#"{"command": "look"}"#))
+            .unwrap();
+        let event = read_event(&mut socket);
+        assert_eq!(event["text"], "You said: look");
+        assert_eq!(event["turn"], 1);
+        socket
+            .send(Message::text(r#"{"command": "quit"}"#))
+            .unwrap();
+        let event = read_event(&mut socket);
+        assert_eq!(event["text"], "Bye.");
+        assert_eq!(event["ended"], true);
+        assert!(matches!(socket.read(), Ok(Message::Close(_))));
+    }
+
+    #[test]
+    fn test_cross_origin() {
+        let addr = start_server();
+        let stream = TcpStream::connect(addr).unwrap();
+        let request = tungstenite::http::Request::builder()
+            .uri(format!("ws://{addr}/ws"))
+            .header("Host", addr.to_string())
+            .header("Origin", "http://example.com")
+            .header("Connection", "Upgrade")
+            .header("Upgrade", "websocket")
+            .header("Sec-WebSocket-Version", "13")
+            .header(
+                "Sec-WebSocket-Key",
+                tungstenite::handshake::client::generate_key(),
+            )
+            .body(())
+            .unwrap();
+        assert!(tungstenite::client(request, stream).is_err());
+    }
+
+    #[test]
+    fn test_dns_rebinding() {
+        let addr = start_server();
+        let stream = TcpStream::connect(addr).unwrap();
+        // A page on evil.example whose domain now resolves to the server
+        let host = format!("evil.example:{}", addr.port());
+        let request = tungstenite::http::Request::builder()
+            .uri(format!("ws://{addr}/ws"))
+            .header("Host", &host)
+            .header("Origin", format!("http://{host}"))
+            .header("Connection", "Upgrade")
+            .header("Upgrade", "websocket")
+            .header("Sec-WebSocket-Version", "13")
+            .header(
+                "Sec-WebSocket-Key",
+                tungstenite::handshake::client::generate_key(),
+            )
+            .body(())
+            .unwrap();
+        assert!(tungstenite::client(request, stream).is_err());
+        let response = get(addr, &format!("GET / HTTP/1.1\r\nHost: {host}\r\n\r\n"));
+        assert!(response.starts_with("HTTP/1.1 403 Forbidden\r\n"));
+    }
+
+    #[test]
+    fn test_trusted_host() {
+        let local = IpAddr::from([192, 168, 1, 2]);
+        let trusted = |host: &str| {
+            let head = format!("GET / HTTP/1.1\r\nHost: {host}\r\n\r\n");
+            Request::read(&mut head.as_bytes())
+                .unwrap()
+                .unwrap()
+                .is_trusted_host(local)
+        };
+        assert!(trusted("localhost"));
+        assert!(trusted("LocalHost:8080"));
+        assert!(trusted("127.0.0.1:8080"));
+        assert!(trusted("[::1]"));
+        assert!(trusted("[::1]:8080"));
+        assert!(trusted("192.168.1.2:8080"));
+        assert!(!trusted("192.168.1.3:8080"));
+        assert!(!trusted("evil.example"));
+        assert!(!trusted("localhost.evil.example:8080"));
+        assert!(!trusted("localhost:http"));
+        assert!(!trusted("[::1]8080"));
+        assert!(!trusted(""));
+    }
+}
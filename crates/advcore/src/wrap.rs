@@ -0,0 +1,199 @@
+use crate::Message;
+use crate::interface::{Interface, InterfaceBuilder, OutputContext};
+use std::io;
+
+/// Word-wrap `text` so that no line is longer than `width` characters, where
+/// possible.  Lines that are already short enough are left untouched, and
+/// lines broken by wrapping keep their original indentation.  A `width` of 0
+/// disables wrapping.
+pub fn wrap(text: &str, width: usize) -> String {
+    if width == 0 {
+        return text.to_owned();
+    }
+    let mut out = Vec::new();
+    for line in text.split('\n') {
+        if line.chars().count() <= width {
+            out.push(line.to_owned());
+            continue;
+        }
+        let body = line.trim_start_matches(' ');
+        let indent = &line[..line.len() - body.len()];
+        let mut current = String::from(indent);
+        // Whether `current` contains any words yet
+        let mut started = false;
+        // Splitting on single spaces means that runs of spaces (such as
+        // between sentences) are preserved as empty words.
+        for word in body.split(' ') {
+            if !started {
+                current.push_str(word);
+                started = !word.is_empty();
+                continue;
+            }
+            if current.chars().count() + 1 + word.chars().count() > width && !word.is_empty() {
+                out.push(current.trim_end().to_owned());
+                current = format!("{indent}{word}");
+            } else {
+                current.push(' ');
+                current.push_str(word);
+            }
+        }
+        out.push(current.trim_end().to_owned());
+    }
+    out.join("\n")
+}
+
+/// An [`InterfaceBuilder`] adapter that word-wraps all output of the wrapped
+/// interface to a given width
+///
+/// If the wrapped interface reports its width (see [`Interface::columns()`])
+/// and that is narrower, output is wrapped to that width instead, so that
+/// output after the player resizes their terminal fits the new size.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct WrapInterfaceBuilder<B> {
+    inner: B,
+    width: usize,
+}
+
+impl<B> WrapInterfaceBuilder<B> {
+    /// Wrap output to `width` characters.  A `width` of 0 disables wrapping.
+    pub fn new(inner: B, width: usize) -> Self {
+        WrapInterfaceBuilder { inner, width }
+    }
+}
+
+impl<B: InterfaceBuilder> InterfaceBuilder for WrapInterfaceBuilder<B> {
+    type Interface = WrapInterface<B::Interface>;
+
+    fn build_interface(self) -> io::Result<Self::Interface> {
+        Ok(WrapInterface {
+            inner: self.inner.build_interface()?,
+            width: self.width,
+        })
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct WrapInterface<I> {
+    inner: I,
+    width: usize,
+}
+
+impl<I> WrapInterface<I> {
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Change the width that subsequent output is wrapped to
+    pub fn set_width(&mut self, width: usize) {
+        self.width = width;
+    }
+}
+
+impl<I: Interface> WrapInterface<I> {
+    // The width to wrap the next output to
+    fn current_width(&mut self) -> usize {
+        match self.inner.columns() {
+            Some(cols) if self.width > 0 && cols > 0 => self.width.min(cols),
+            _ => self.width,
+        }
+    }
+}
+
+impl<I: Interface> Interface for WrapInterface<I> {
+    fn show_output(&mut self, text: &str) -> io::Result<()> {
+        let width = self.current_width();
+        self.inner.show_output(&wrap(text, width))
+    }
+
+    fn show_output_with_context(&mut self, text: &str, ctx: &OutputContext) -> io::Result<()> {
+        let width = self.current_width();
+        self.inner.show_output_with_context(&wrap(text, width), ctx)
+    }
+
+    fn show_messages(&mut self, messages: &[Message], ctx: &OutputContext) -> io::Result<()> {
+        let width = self.current_width();
+        let messages = messages
+            .iter()
+            .map(|m| Message::new(m.kind, wrap(&m.text, width)))
+            .collect::<Vec<_>>();
+        self.inner.show_messages(&messages, ctx)
+    }
+
+    fn get_input(&mut self) -> io::Result<Option<String>> {
+        self.inner.get_input()
+    }
+
+    fn columns(&mut self) -> Option<usize> {
+        self.inner.columns()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(
+            wrap("You are in the center room.  Doors lead out.", 20),
+            "You are in the\ncenter room.  Doors\nlead out."
+        );
+        assert_eq!(
+            wrap("Short line\n\n    indented text that is long", 16),
+            "Short line\n\n    indented\n    text that is\n    long"
+        );
+        assert_eq!(
+            wrap("a supercalifragilistic word", 10),
+            "a\nsupercalifragilistic\nword"
+        );
+        assert_eq!(wrap("No wrapping at all", 0), "No wrapping at all");
+    }
+
+    // An interface that records its output and reports a changeable width
+    #[derive(Debug, Default)]
+    struct Resizable {
+        output: Vec<String>,
+        columns: Option<usize>,
+    }
+
+    impl Interface for Resizable {
+        fn show_output(&mut self, text: &str) -> io::Result<()> {
+            self.output.push(text.to_owned());
+            Ok(())
+        }
+
+        fn get_input(&mut self) -> io::Result<Option<String>> {
+            Ok(None)
+        }
+
+        fn columns(&mut self) -> Option<usize> {
+            self.columns
+        }
+    }
+
+    #[test]
+    fn test_wrap_to_columns() {
+        let mut iface = WrapInterface {
+            inner: Resizable::default(),
+            width: 20,
+        };
+        let text = "You are in the center room.";
+        iface.show_output(text).unwrap();
+        iface.inner.columns = Some(12);
+        iface.show_output(text).unwrap();
+        iface.inner.columns = Some(80);
+        iface.show_output(text).unwrap();
+        iface.width = 0;
+        iface.inner.columns = Some(12);
+        iface.show_output(text).unwrap();
+        assert_eq!(
+            iface.inner.output,
+            [
+                "You are in the\ncenter room.",
+                "You are in\nthe center\nroom.",
+                "You are in the\ncenter room.",
+                "You are in the center room.",
+            ]
+        );
+    }
+}
@@ -1,9 +1,13 @@
 use super::entities::Entity;
-use super::rooms::Room;
-use super::vocab::{Action, Motion, Word};
+use super::rooms::{Room, Verbosity};
+use super::vocab::{Action, Command, Motion, Rule, Slot, Word};
 use phf::{Map, phf_map};
 use unicase::UniCase;
 
+/// Abbreviations of vocabulary words must be at least this many characters
+/// long
+pub(crate) const MIN_ABBREVIATION_LENGTH: usize = 3;
+
 pub(crate) static VOCABULARY: Map<UniCase<&'static str>, Word> = phf_map! {
     UniCase::ascii("N") => Word::Motion(Motion::North),
     UniCase::ascii("NORTH") => Word::Motion(Motion::North),
@@ -13,6 +17,32 @@ pub(crate) static VOCABULARY: Map<UniCase<&'static str>, Word> = phf_map! {
     UniCase::ascii("EAST") => Word::Motion(Motion::East),
     UniCase::ascii("W") => Word::Motion(Motion::West),
     UniCase::ascii("WEST") => Word::Motion(Motion::West),
+    UniCase::ascii("NE") => Word::Motion(Motion::NorthEast),
+    UniCase::ascii("NORTHEAST") => Word::Motion(Motion::NorthEast),
+    UniCase::ascii("NORTH-EAST") => Word::Motion(Motion::NorthEast),
+    UniCase::ascii("NW") => Word::Motion(Motion::NorthWest),
+    UniCase::ascii("NORTHWEST") => Word::Motion(Motion::NorthWest),
+    UniCase::ascii("NORTH-WEST") => Word::Motion(Motion::NorthWest),
+    UniCase::ascii("SE") => Word::Motion(Motion::SouthEast),
+    UniCase::ascii("SOUTHEAST") => Word::Motion(Motion::SouthEast),
+    UniCase::ascii("SOUTH-EAST") => Word::Motion(Motion::SouthEast),
+    UniCase::ascii("SW") => Word::Motion(Motion::SouthWest),
+    UniCase::ascii("SOUTHWEST") => Word::Motion(Motion::SouthWest),
+    UniCase::ascii("SOUTH-WEST") => Word::Motion(Motion::SouthWest),
+    UniCase::ascii("U") => Word::Motion(Motion::Up),
+    UniCase::ascii("UP") => Word::Motion(Motion::Up),
+    UniCase::ascii("D") => Word::Motion(Motion::Down),
+    UniCase::ascii("DOWN") => Word::Motion(Motion::Down),
+    UniCase::ascii("IN") => Word::Motion(Motion::In),
+    UniCase::ascii("INSIDE") => Word::Motion(Motion::In),
+    UniCase::ascii("ENTER") => Word::Motion(Motion::In),
+    UniCase::ascii("OUT") => Word::Motion(Motion::Out),
+    UniCase::ascii("OUTSIDE") => Word::Motion(Motion::Out),
+    UniCase::ascii("CENTER") => Word::Place(Room::Center),
+    UniCase::ascii("CENTRE") => Word::Place(Room::Center),
+    UniCase::ascii("MIDDLE") => Word::Place(Room::Center),
+    UniCase::ascii("GO") => Word::Go,
+    UniCase::ascii("TO") => Word::To,
     UniCase::ascii("EXAMINE") => Word::Action(Action::Examine),
     UniCase::ascii("DESCRIBE") => Word::Action(Action::Examine),
     UniCase::ascii("LOOK") => Word::Look,
@@ -23,6 +53,18 @@ pub(crate) static VOCABULARY: Map<UniCase<&'static str>, Word> = phf_map! {
     UniCase::ascii("BACK") => Word::Action(Action::Back),
     UniCase::ascii("RETURN") => Word::Action(Action::Back),
     UniCase::ascii("RETREAT") => Word::Action(Action::Back),
+    UniCase::ascii("AGAIN") => Word::Action(Action::Again),
+    UniCase::ascii("G") => Word::Action(Action::Again),
+    UniCase::ascii("OOPS") => Word::Oops,
+    UniCase::ascii("VERBOSE") => Word::Action(Action::Verbose),
+    UniCase::ascii("BRIEF") => Word::Action(Action::Brief),
+    UniCase::ascii("SUPERBRIEF") => Word::Action(Action::Superbrief),
+    UniCase::ascii("EXITS") => Word::Action(Action::Exits),
+    UniCase::ascii("MAP") => Word::Action(Action::Map),
+    UniCase::ascii("ON") => Word::On,
+    UniCase::ascii("OFF") => Word::Off,
+    UniCase::ascii("SAVE") => Word::Action(Action::Save),
+    UniCase::ascii("HELP") => Word::Action(Action::Help),
     UniCase::ascii("QUIT") => Word::Action(Action::Quit),
     UniCase::ascii("EXIT") => Word::Action(Action::Quit),
     UniCase::ascii("BANQUET") => Word::Entity(Entity::Banquet),
@@ -52,7 +94,212 @@ pub(crate) static VOCABULARY: Map<UniCase<&'static str>, Word> = phf_map! {
     UniCase::ascii("FLOOR") => Word::Entity(Entity::TicTacToe),
 };
 
-pub(crate) const TRAVEL_TABLE: [((Room, Motion), Room); 24] = [
+const GO: Slot = Slot::Word(Word::Go);
+const TO: Slot = Slot::Word(Word::To);
+const EXAMINE: Slot = Slot::Word(Word::Action(Action::Examine));
+const LOOK: Slot = Slot::Word(Word::Look);
+const AT: Slot = Slot::Word(Word::At);
+const ROOM: Slot = Slot::Word(Word::Room);
+const READ: Slot = Slot::Word(Word::Action(Action::Read));
+const BACK: Slot = Slot::Word(Word::Action(Action::Back));
+const AGAIN: Slot = Slot::Word(Word::Action(Action::Again));
+const VERBOSE: Slot = Slot::Word(Word::Action(Action::Verbose));
+const BRIEF: Slot = Slot::Word(Word::Action(Action::Brief));
+const SUPERBRIEF: Slot = Slot::Word(Word::Action(Action::Superbrief));
+const EXITS: Slot = Slot::Word(Word::Action(Action::Exits));
+const MAP: Slot = Slot::Word(Word::Action(Action::Map));
+const SAVE: Slot = Slot::Word(Word::Action(Action::Save));
+const HELP: Slot = Slot::Word(Word::Action(Action::Help));
+const QUIT: Slot = Slot::Word(Word::Action(Action::Quit));
+
+pub(crate) static GRAMMAR: [Rule; 27] = [
+    Rule {
+        verb: "go",
+        pattern: &[Slot::Motion],
+        build: |c| c.motion.map(Command::Motion),
+    },
+    Rule {
+        verb: "go",
+        pattern: &[GO, Slot::Motion],
+        build: |c| c.motion.map(Command::Motion),
+    },
+    Rule {
+        verb: "go to",
+        pattern: &[GO, TO, Slot::Place],
+        build: |c| c.room.map(Command::GoTo),
+    },
+    Rule {
+        verb: "go to",
+        pattern: &[GO, TO, Slot::Place, ROOM],
+        build: |c| c.room.map(Command::GoTo),
+    },
+    Rule {
+        verb: "examine",
+        pattern: &[EXAMINE],
+        build: |_| Some(Command::Examine(None)),
+    },
+    Rule {
+        verb: "examine",
+        pattern: &[EXAMINE, ROOM],
+        build: |_| Some(Command::Look),
+    },
+    Rule {
+        verb: "examine",
+        pattern: &[EXAMINE, Slot::Entity],
+        build: |c| c.entity.map(|en| Command::Examine(Some(en))),
+    },
+    Rule {
+        verb: "look at",
+        pattern: &[LOOK],
+        build: |_| Some(Command::Look),
+    },
+    Rule {
+        verb: "look at",
+        pattern: &[LOOK, ROOM],
+        build: |_| Some(Command::Look),
+    },
+    Rule {
+        verb: "look at",
+        pattern: &[LOOK, Slot::Entity],
+        build: |c| c.entity.map(|en| Command::Examine(Some(en))),
+    },
+    Rule {
+        verb: "look at",
+        pattern: &[LOOK, AT, ROOM],
+        build: |_| Some(Command::Look),
+    },
+    Rule {
+        verb: "look at",
+        pattern: &[LOOK, AT, Slot::Entity],
+        build: |c| c.entity.map(|en| Command::Examine(Some(en))),
+    },
+    Rule {
+        verb: "read",
+        pattern: &[READ],
+        build: |_| Some(Command::Read(None)),
+    },
+    Rule {
+        verb: "read",
+        pattern: &[READ, Slot::Entity],
+        build: |c| c.entity.map(|en| Command::Read(Some(en))),
+    },
+    Rule {
+        verb: "go back",
+        pattern: &[BACK],
+        build: |_| Some(Command::Back),
+    },
+    Rule {
+        verb: "repeat",
+        pattern: &[AGAIN],
+        build: |_| Some(Command::Again),
+    },
+    Rule {
+        verb: "use verbose descriptions",
+        pattern: &[VERBOSE],
+        build: |_| Some(Command::SetVerbosity(Verbosity::Verbose)),
+    },
+    Rule {
+        verb: "use brief descriptions",
+        pattern: &[BRIEF],
+        build: |_| Some(Command::SetVerbosity(Verbosity::Brief)),
+    },
+    Rule {
+        verb: "use superbrief descriptions",
+        pattern: &[SUPERBRIEF],
+        build: |_| Some(Command::SetVerbosity(Verbosity::Superbrief)),
+    },
+    Rule {
+        verb: "list exits",
+        pattern: &[EXITS],
+        build: |_| Some(Command::Exits),
+    },
+    Rule {
+        verb: "list exits",
+        pattern: &[EXITS, Slot::Word(Word::On)],
+        build: |_| Some(Command::AutoExits(true)),
+    },
+    Rule {
+        verb: "list exits",
+        pattern: &[EXITS, Slot::Word(Word::Off)],
+        build: |_| Some(Command::AutoExits(false)),
+    },
+    Rule {
+        verb: "see the map",
+        pattern: &[MAP],
+        build: |_| Some(Command::Map),
+    },
+    Rule {
+        verb: "save",
+        pattern: &[SAVE],
+        build: |_| Some(Command::Save),
+    },
+    Rule {
+        verb: "get help with",
+        pattern: &[HELP],
+        build: |_| Some(Command::Help(None)),
+    },
+    Rule {
+        verb: "get help with",
+        pattern: &[HELP, Slot::Any],
+        build: |c| c.word.map(|w| Command::Help(Some(w))),
+    },
+    Rule {
+        verb: "quit",
+        pattern: &[QUIT],
+        build: |_| Some(Command::Quit),
+    },
+];
+
+/// The commands listed by HELP, in order, identified by the first slot of
+/// their grammar rules, along with a summary of each
+pub(crate) static HELP_TOPICS: [(Slot, &str); 16] = [
+    (Slot::Motion, "Move in the given direction"),
+    (
+        GO,
+        "Move in the given direction, or walk to a room you've already visited by the shortest route",
+    ),
+    (BACK, "Return to the previous room"),
+    (
+        LOOK,
+        "Describe the current room in full, or look closely at an object",
+    ),
+    (EXAMINE, "Look closely at an object"),
+    (READ, "Read a readable object"),
+    (AGAIN, "Repeat your last command"),
+    (
+        Slot::Word(Word::Oops),
+        "Type OOPS followed by a word to use it in place of the word I didn't understand in your last command",
+    ),
+    (VERBOSE, "Always describe rooms in full"),
+    (
+        BRIEF,
+        "Only describe rooms in full the first time you enter them (the default)",
+    ),
+    (
+        SUPERBRIEF,
+        "Only show the names of rooms, not their descriptions or contents",
+    ),
+    (
+        EXITS,
+        "List the ways out of the current room, or turn listing them in room descriptions on or off",
+    ),
+    (
+        MAP,
+        "Show a map of the rooms you've visited, with your location marked",
+    ),
+    (
+        SAVE,
+        "Save your progress so that you can pick up where you left off",
+    ),
+    (HELP, "List the commands, or explain how to use one"),
+    (QUIT, "Quit the game"),
+];
+
+/// Usage lines for HELP for commands that aren't parsed with `GRAMMAR`,
+/// identified by their first slot
+pub(crate) static EXTRA_USAGES: [(Slot, &str); 1] = [(Slot::Word(Word::Oops), "OOPS ⟨word⟩")];
+
+pub(crate) const TRAVEL_TABLE: [((Room, Motion), Room); 32] = [
     ((Room::NorthWest, Motion::East), Room::North),
     ((Room::NorthWest, Motion::South), Room::West),
     ((Room::North, Motion::West), Room::NorthWest),
@@ -77,6 +324,14 @@ pub(crate) const TRAVEL_TABLE: [((Room, Motion), Room); 24] = [
     ((Room::South, Motion::East), Room::SouthEast),
     ((Room::SouthEast, Motion::West), Room::South),
     ((Room::SouthEast, Motion::North), Room::East),
+    ((Room::Center, Motion::NorthWest), Room::NorthWest),
+    ((Room::Center, Motion::NorthEast), Room::NorthEast),
+    ((Room::Center, Motion::SouthWest), Room::SouthWest),
+    ((Room::Center, Motion::SouthEast), Room::SouthEast),
+    ((Room::NorthWest, Motion::SouthEast), Room::Center),
+    ((Room::NorthEast, Motion::SouthWest), Room::Center),
+    ((Room::SouthWest, Motion::NorthEast), Room::Center),
+    ((Room::SouthEast, Motion::NorthWest), Room::Center),
 ];
 
 pub(crate) const FIXED_ENTITIES: [(Entity, Room); 9] = [
@@ -1,3 +1,6 @@
+use super::rooms::Room;
+use std::collections::HashMap;
+
 #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub(crate) enum Entity {
     Banquet,
@@ -12,6 +15,36 @@ pub(crate) enum Entity {
 }
 
 impl Entity {
+    // Entities with lower values are listed first in room descriptions
+    pub(crate) fn priority(&self) -> u8 {
+        match self {
+            Entity::Banquet => 10,
+            Entity::Books => 10,
+            Entity::Cushions => 20,
+            Entity::Globe => 10,
+            Entity::Mural => 20,
+            Entity::Painting => 10,
+            Entity::PenguinPhoto => 20,
+            Entity::SecretPlans => 30,
+            Entity::TicTacToe => 10,
+        }
+    }
+
+    // How to refer to the entity in a sentence
+    pub(crate) fn name(&self) -> &str {
+        match self {
+            Entity::Banquet => "the banquet",
+            Entity::Books => "the books",
+            Entity::Cushions => "the cushions",
+            Entity::Globe => "the globe",
+            Entity::Mural => "the mural",
+            Entity::Painting => "the painting",
+            Entity::PenguinPhoto => "the photograph",
+            Entity::SecretPlans => "the secret plans",
+            Entity::TicTacToe => "the tic-tac-toe grid",
+        }
+    }
+
     // Text displayed for the entity when describing the containing room
     pub(crate) fn describe(&self) -> &str {
         match self {
@@ -63,3 +96,43 @@ impl Entity {
         }
     }
 }
+
+/// The locations of entities, indexed by room.  The entities in each room are
+/// kept sorted by listing priority.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub(crate) struct Placements {
+    by_room: HashMap<Room, Vec<Entity>>,
+    locations: HashMap<Entity, Room>,
+}
+
+impl Placements {
+    pub(crate) fn new<I: IntoIterator<Item = (Entity, Room)>>(iter: I) -> Placements {
+        let mut placements = Placements::default();
+        for (en, room) in iter {
+            placements.place(en, room);
+        }
+        placements
+    }
+
+    /// Returns the entities in `room` in the order in which they should be
+    /// listed
+    pub(crate) fn in_room(&self, room: Room) -> &[Entity] {
+        self.by_room.get(&room).map_or(&[], Vec::as_slice)
+    }
+
+    pub(crate) fn is_in(&self, en: Entity, room: Room) -> bool {
+        self.locations.get(&en) == Some(&room)
+    }
+
+    /// Put `en` in `room`, removing it from wherever it was before
+    pub(crate) fn place(&mut self, en: Entity, room: Room) {
+        if let Some(old) = self.locations.insert(en, room) {
+            if let Some(ents) = self.by_room.get_mut(&old) {
+                ents.retain(|&e| e != en);
+            }
+        }
+        let ents = self.by_room.entry(room).or_default();
+        let i = ents.partition_point(|e| (e.priority(), *e) < (en.priority(), en));
+        ents.insert(i, en);
+    }
+}
@@ -0,0 +1,101 @@
+use super::data::{EXTRA_USAGES, GRAMMAR, HELP_TOPICS, VOCABULARY};
+use super::vocab::{Slot, Word};
+
+/// The line shown at the start of the game and after unknown words
+pub(crate) const HELP_HINT: &str = "(Type HELP for a list of commands.)";
+
+/// Produce the text for the HELP command, either listing all commands or, if
+/// `topic` is given, describing how to use the command starting with that word
+pub(crate) fn help(topic: Option<Word>) -> String {
+    let Some(word) = topic else {
+        let mut lines = vec![
+            String::from("I understand the following commands:"),
+            String::new(),
+        ];
+        for &(head, summary) in &HELP_TOPICS {
+            lines.push(format!("{} — {summary}", synonyms(head).join(", ")));
+        }
+        lines.push(String::new());
+        lines.push(String::from(
+            "Type HELP followed by a command to see how to use it.",
+        ));
+        return lines.join("\n");
+    };
+    let Some(&(head, summary)) = HELP_TOPICS.iter().find(|(head, _)| head.matches(word)) else {
+        return String::from("That isn't a command.  Type HELP for a list of commands.");
+    };
+    let mut text = format!("{} — {summary}", synonyms(head).join(", "));
+    let usages = GRAMMAR
+        .iter()
+        .filter(|rule| rule.pattern.first() == Some(&head))
+        .map(|rule| {
+            rule.pattern
+                .iter()
+                .map(|&slot| placeholder(slot))
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .chain(
+            EXTRA_USAGES
+                .iter()
+                .filter(|&&(slot, _)| slot == head)
+                .map(|&(_, usage)| usage.to_owned()),
+        )
+        .collect::<Vec<_>>();
+    if !usages.is_empty() {
+        text.push_str("\n\nUsage:");
+        for u in usages {
+            text.push_str("\n    ");
+            text.push_str(&u);
+        }
+    }
+    text
+}
+
+impl Slot {
+    /// Returns whether the slot can be filled by the given word
+    fn matches(self, word: Word) -> bool {
+        match self {
+            Slot::Word(w) => w == word,
+            Slot::Motion => matches!(word, Word::Motion(_)),
+            Slot::Entity => matches!(word, Word::Entity(_)),
+            Slot::Place => matches!(word, Word::Place(_) | Word::Motion(_)),
+            Slot::Any => true,
+        }
+    }
+}
+
+// All vocabulary words that can fill `slot`, with the preferred spelling of
+// each word first
+fn synonyms(slot: Slot) -> Vec<&'static str> {
+    let mut words = VOCABULARY
+        .entries()
+        .filter(|&(_, &w)| slot.matches(w))
+        .map(|(k, &w)| (w, k.into_inner()))
+        .collect::<Vec<_>>();
+    // Prefer short words, but put single-letter abbreviations and words that
+    // aren't made of letters (like "@") last.
+    words.sort_unstable_by_key(|&(w, s)| {
+        (
+            w,
+            !s.chars().all(|c| c.is_ascii_alphabetic()),
+            s.len() == 1,
+            s.len(),
+            s,
+        )
+    });
+    words.into_iter().map(|(_, s)| s).collect()
+}
+
+// How to show `slot` in a usage line
+fn placeholder(slot: Slot) -> String {
+    match slot {
+        Slot::Word(w) => synonyms(Slot::Word(w))
+            .first()
+            .map_or_else(String::new, |&s| s.to_owned()),
+        Slot::Motion => String::from("⟨direction⟩"),
+        Slot::Entity => String::from("⟨object⟩"),
+        Slot::Place => String::from("⟨room⟩"),
+        Slot::Any => String::from("⟨command⟩"),
+    }
+}
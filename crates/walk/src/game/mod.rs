@@ -1,33 +1,89 @@
 mod data;
 mod entities;
+mod help;
 mod rooms;
+mod save;
 mod vocab;
-use self::entities::Entity;
-use self::rooms::Room;
-use self::vocab::{Command, Motion};
-use advcore::{GameBuilder, GameEngine, Output};
-use std::collections::{HashMap, HashSet};
+use self::entities::{Entity, Placements};
+use self::help::{HELP_HINT, help};
+use self::rooms::{Room, Verbosity};
+pub(crate) use self::save::Restored;
+use self::vocab::{Command, CommandError, Motion, Verb, WordError};
+use advcore::{
+    Correction, GameBuilder, GameEngine, GridMap, Message, Output, Speller, WorldGraph,
+    english_list,
+};
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::path::PathBuf;
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
-pub(crate) struct Builder;
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub(crate) struct Builder {
+    seed: Option<u64>,
+}
+
+impl Builder {
+    /// Set the seed for the game's random elements.  walk doesn't have any
+    /// yet, so the seed is only recorded in save files.
+    pub(crate) fn seed(mut self, seed: Option<u64>) -> Builder {
+        self.seed = seed;
+        self
+    }
+}
 
 impl GameBuilder for Builder {
     type Engine = Game;
 
     fn start(self) -> Output<Game> {
         let mut game = Game::new();
-        let text = game.show_location(None);
-        Output::Continue { game, text }
+        game.seed = self.seed;
+        let text = format!("{}\n\n{HELP_HINT}", game.show_location(false));
+        Output::continue_with(game, text)
     }
 }
 
+/// Build a graph of the game's rooms, exits, and the entities in each room
+pub(crate) fn world_graph() -> WorldGraph {
+    let rooms = data::TRAVEL_TABLE
+        .iter()
+        .flat_map(|&((src, _), dest)| [src, dest])
+        .chain(data::FIXED_ENTITIES.iter().map(|&(_, room)| room))
+        .collect::<BTreeSet<_>>();
+    let placements = Placements::new(data::FIXED_ENTITIES);
+    let mut graph = WorldGraph::new();
+    for room in rooms {
+        let id = format!("{room:?}");
+        graph.add_node(&id, room.name());
+        for en in placements.in_room(room) {
+            graph.annotate(&id, en.name());
+        }
+    }
+    for ((src, m), dest) in data::TRAVEL_TABLE {
+        graph.add_edge(&format!("{src:?}"), &format!("{dest:?}"), m.name());
+    }
+    graph
+}
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub(crate) struct Game {
     travel: HashMap<(Room, Motion), Room>,
     location: Room,
     prev_location: Option<Room>,
     visited: HashSet<Room>,
-    fixed: HashMap<Entity, Room>,
+    placements: Placements,
+    verbosity: Verbosity,
+    // Whether to list the exits when describing a room
+    show_exits: bool,
+    // The most recent input that wasn't blank, AGAIN, or OOPS, for use by
+    // AGAIN and OOPS
+    last_input: Option<String>,
+    // The error (if any) that resulted from parsing `last_input`
+    last_error: Option<CommandError>,
+    speller: Speller,
+    // The random seed given on the command line, if any
+    seed: Option<u64>,
+    // The file that the game was loaded from or saved to, if any, which SAVE
+    // and autosaving write to
+    save_file: Option<PathBuf>,
 }
 
 impl Game {
@@ -35,99 +91,434 @@ impl Game {
         let travel = HashMap::from(data::TRAVEL_TABLE);
         let location = Room::Center;
         let visited = HashSet::new();
-        let fixed = HashMap::from(data::FIXED_ENTITIES);
+        let placements = Placements::new(data::FIXED_ENTITIES);
         Game {
             travel,
             location,
             prev_location: None,
             visited,
-            fixed,
+            placements,
+            verbosity: Verbosity::Brief,
+            show_exits: true,
+            last_input: None,
+            last_error: None,
+            speller: Speller::new(),
+            seed: None,
+            save_file: None,
         }
     }
 
-    fn show_location(&mut self, long: Option<bool>) -> String {
-        let long = long.unwrap_or_else(|| self.visited.insert(self.location));
-        let mut s = if long {
-            self.location.long_description().to_owned()
+    // Describe the current room.  `look` is true if the player explicitly
+    // asked for a description, in which case it is always given in full.
+    fn show_location(&mut self, look: bool) -> String {
+        let first_visit = self.visited.insert(self.location);
+        let mode = if look {
+            Verbosity::Verbose
         } else {
-            self.location.short_description().to_owned()
+            self.verbosity
+        };
+        let mut s = match mode {
+            Verbosity::Verbose => self.location.long_description().to_owned(),
+            Verbosity::Brief if first_visit => self.location.long_description().to_owned(),
+            Verbosity::Brief => self.location.short_description().to_owned(),
+            Verbosity::Superbrief => return self.location.name().to_owned(),
         };
-        for (&en, &rm) in &self.fixed {
-            if rm == self.location {
-                s.push('\n');
-                s.push('\n');
-                s.push_str(en.describe());
+        // The most prominent entity gets its own paragraph, and the rest are
+        // simply listed.
+        if let Some((first, rest)) = self.placements.in_room(self.location).split_first() {
+            s.push_str("\n\n");
+            s.push_str(first.describe());
+            if !rest.is_empty() {
+                let names = rest.iter().map(Entity::name).collect::<Vec<_>>();
+                s.push_str("\n\nYou can also see ");
+                s.push_str(&english_list(&names, "and"));
+                s.push_str(" here.");
             }
         }
+        if self.show_exits {
+            s.push_str("\n\n");
+            s.push_str(&self.list_exits());
+        }
         s
     }
 
+    // Returns the directions one can travel from `room` and where they lead,
+    // in order of direction
+    fn exits_from(&self, room: Room) -> Vec<(Motion, Room)> {
+        let mut exits = self
+            .travel
+            .iter()
+            .filter(|&(&(src, _), _)| src == room)
+            .map(|(&(_, m), &dest)| (m, dest))
+            .collect::<Vec<_>>();
+        exits.sort_unstable();
+        exits
+    }
+
+    // Describe the ways out of the current room, naming the destinations that
+    // the player has already visited
+    fn list_exits(&self) -> String {
+        let exits = self.exits_from(self.location);
+        if exits.is_empty() {
+            return String::from("There are no exits.");
+        }
+        let exits = exits
+            .into_iter()
+            .map(|(m, dest)| {
+                if self.visited.contains(&dest) {
+                    format!("{} (to the {})", m.name(), dest.name().to_ascii_lowercase())
+                } else {
+                    m.name().to_owned()
+                }
+            })
+            .collect::<Vec<_>>();
+        format!("Exits: {}.", exits.join(", "))
+    }
+
+    // Draw a map of the rooms the player has visited
+    fn show_map(&self) -> String {
+        let mut visited = self.visited.iter().copied().collect::<Vec<_>>();
+        visited.sort_unstable();
+        let mut map = GridMap::new();
+        for &room in &visited {
+            map.add_room(
+                Some(room.coords()),
+                room.abbreviation(),
+                room == self.location,
+            );
+            for (_, dest) in self.exits_from(room) {
+                if self.visited.contains(&dest) {
+                    map.connect(room.coords(), dest.coords());
+                }
+            }
+        }
+        map.render()
+    }
+
     fn move_to(&mut self, room: Room) -> String {
         self.prev_location = Some(self.location);
         self.location = room;
-        self.show_location(None)
+        self.show_location(false)
     }
-}
 
-impl GameEngine for Game {
-    fn handle_input(mut self, input: &str) -> Output<Self> {
-        let text = match input.parse::<Command>() {
-            Ok(Command::Motion(m)) => {
-                if let Some(room) = self.travel.get(&(self.location, m)).copied() {
-                    self.move_to(room)
-                } else {
-                    String::from("There's no way to go in that direction.")
+    // Find the shortest route from the current room to `dest` that only passes
+    // through rooms the player has already visited
+    fn route_to(&self, dest: Room) -> Option<Vec<Motion>> {
+        let mut came_from = HashMap::new();
+        let mut queue = VecDeque::from([self.location]);
+        while let Some(room) = queue.pop_front() {
+            if room == dest {
+                let mut route = Vec::new();
+                let mut here = dest;
+                while here != self.location {
+                    let (prev, m) = came_from.get(&here).copied()?;
+                    route.push(m);
+                    here = prev;
                 }
+                route.reverse();
+                return Some(route);
             }
-            Ok(Command::Examine(None)) => self.show_location(Some(true)),
-            Ok(Command::Examine(Some(en))) => {
-                if self.fixed.get(&en) == Some(&self.location) {
-                    en.examine().to_owned()
-                } else {
-                    String::from("That isn't here.")
+            for (m, next) in self.exits_from(room) {
+                if self.visited.contains(&next)
+                    && next != self.location
+                    && !came_from.contains_key(&next)
+                {
+                    came_from.insert(next, (room, m));
+                    queue.push_back(next);
+                }
+            }
+        }
+        None
+    }
+
+    // Walk to `dest` one room at a time, describing each room along the way
+    fn go_to(&mut self, dest: Room) -> String {
+        if dest == self.location {
+            return String::from("You're already there.");
+        }
+        let Some(route) = self.route_to(dest) else {
+            return String::from("You don't know how to get there.");
+        };
+        let mut steps = Vec::with_capacity(route.len());
+        for m in route {
+            // Stop if the way turns out to be blocked.
+            let Some(room) = self.travel.get(&(self.location, m)).copied() else {
+                steps.push(String::from("Your way is blocked."));
+                break;
+            };
+            steps.push(self.move_to(room));
+        }
+        steps.join("\n\n")
+    }
+
+    // Determine what entity `verb` should be applied to, given the object (if
+    // any) that the player specified
+    fn target(&self, verb: Verb, object: Option<Entity>) -> Target {
+        if let Some(entity) = object {
+            if !self.placements.is_in(entity, self.location) {
+                Target::Failed(String::from("That isn't here."))
+            } else if !verb.applies_to(entity) {
+                Target::Failed(format!("You can't {} that.", verb.name()))
+            } else {
+                Target::Found {
+                    entity,
+                    note: String::new(),
                 }
             }
-            Ok(Command::Read(None)) => self
-                .fixed
+        } else {
+            let candidates = self
+                .placements
+                .in_room(self.location)
                 .iter()
-                .find_map(|(&en, &room)| {
-                    if room == self.location {
-                        en.read()
-                    } else {
-                        None
-                    }
-                })
-                .unwrap_or("There's nothing here to read.")
-                .to_owned(),
-            Ok(Command::Read(Some(en))) => {
-                if self.fixed.get(&en) == Some(&self.location) {
-                    if let Some(desc) = en.read() {
-                        desc.to_owned()
-                    } else {
-                        String::from("You can't read that.")
-                    }
-                } else {
-                    String::from("That isn't here.")
+                .copied()
+                .filter(|&en| verb.applies_to(en))
+                .collect::<Vec<_>>();
+            match candidates.as_slice() {
+                [] => Target::Nothing,
+                [entity] => Target::Found {
+                    entity: *entity,
+                    note: format!("({})\n", entity.name()),
+                },
+                _ => Target::Failed(format!("What do you want to {}?", verb.name())),
+            }
+        }
+    }
+
+    // Repeat the previous command
+    fn again(self, mut messages: Vec<Message>) -> Output<Self> {
+        if let Some(prev) = self.last_input.clone() {
+            let r = prev.parse::<Command>();
+            self.dispatch(prev, r, messages, false)
+        } else {
+            messages.push(Message::parser_error("You haven't done anything yet."));
+            Output::Continue {
+                game: self,
+                messages,
+            }
+        }
+    }
+
+    // Retry the previous command with its unknown word replaced by `word`
+    fn replace(self, word: &str, mut messages: Vec<Message>) -> Output<Self> {
+        if let Some(fixed) = self.oops(word) {
+            let r = fixed.parse::<Command>();
+            self.dispatch(fixed, r, messages, true)
+        } else {
+            messages.push(Message::parser_error("There was no word to replace!"));
+            Output::Continue {
+                game: self,
+                messages,
+            }
+        }
+    }
+
+    // Returns the previous input with its unknown word replaced by `word`
+    fn oops(&self, word: &str) -> Option<String> {
+        let Some(CommandError::Word(e)) = self.last_error.as_ref() else {
+            return None;
+        };
+        let prev = self.last_input.as_deref()?;
+        Some(replace_word(prev, e.word(), word))
+    }
+
+    // Carry out the command `r` parsed from `input`, after correcting any
+    // misspellings.  The messages for any corrections are appended to
+    // `corrections`.  `replaced` is true if `input` was produced by OOPS.
+    fn dispatch(
+        mut self,
+        mut input: String,
+        mut r: Result<Command, CommandError>,
+        mut corrections: Vec<Message>,
+        replaced: bool,
+    ) -> Output<Self> {
+        let mut suggestions = Vec::new();
+        while let Err(CommandError::Word(WordError::Unknown(word))) = &r {
+            let vocab = data::VOCABULARY.keys().map(AsRef::as_ref);
+            match self.speller.correct(word, vocab) {
+                Correction::Replace(w) => {
+                    corrections.push(Message::system(format!("(assuming {w})")));
+                    input = replace_word(&input, word, w);
+                    r = input.parse::<Command>();
                 }
+                Correction::Suggest(ws) => {
+                    suggestions = ws;
+                    break;
+                }
+                Correction::Unknown => break,
+            }
+        }
+        if !matches!(r, Ok(Command::Nop | Command::Again | Command::Oops(_))) {
+            // Normalized so that it fits on one line of a save file
+            self.last_input = Some(input.split_whitespace().collect::<Vec<_>>().join(" "));
+            self.last_error = r.as_ref().err().cloned();
+        }
+        let message = match r {
+            Ok(Command::Again | Command::Oops(_)) if replaced => {
+                Message::parser_error(OOPS_META_ERROR)
             }
-            Ok(Command::Back) => {
-                if let Some(prev) = self.prev_location {
-                    self.move_to(prev)
+            Ok(Command::Again) => return self.again(corrections),
+            Ok(Command::Oops(word)) => return self.replace(&word, corrections),
+            Ok(Command::Motion(m)) => Message::narrative(
+                if let Some(room) = self.travel.get(&(self.location, m)).copied() {
+                    self.move_to(room)
                 } else {
-                    String::from("You weren't anywhere else before here.")
-                }
+                    format!("There's no way to go {} from here.", m.name())
+                },
+            ),
+            Ok(Command::GoTo(room)) => Message::narrative(self.go_to(room)),
+            Ok(Command::Look) => Message::narrative(self.show_location(true)),
+            Ok(Command::Examine(obj)) => {
+                Message::narrative(match self.target(Verb::Examine, obj) {
+                    Target::Found { entity, note } => note + entity.examine(),
+                    Target::Nothing => self.show_location(true),
+                    Target::Failed(msg) => msg,
+                })
             }
+            Ok(Command::Read(obj)) => Message::narrative(match self.target(Verb::Read, obj) {
+                Target::Found { entity, note } => note + entity.read().unwrap_or_default(),
+                Target::Nothing => String::from("There's nothing here to read."),
+                Target::Failed(msg) => msg,
+            }),
+            Ok(Command::Back) => Message::narrative(if let Some(prev) = self.prev_location {
+                self.move_to(prev)
+            } else {
+                String::from("You weren't anywhere else before here.")
+            }),
             Ok(Command::Quit) => {
-                return Output::Goodbye {
-                    text: String::from("Be seeing you..."),
+                let mut messages = corrections;
+                messages.push(Message::system("Are you sure you want to quit?"));
+                return Output::Confirm {
+                    game: self,
+                    messages,
                 };
             }
-            Ok(Command::Nop) => String::new(),
-            Err(e) => e.to_string(),
+            Ok(Command::SetVerbosity(v)) => {
+                self.verbosity = v;
+                Message::system(v.announce())
+            }
+            Ok(Command::Exits) => Message::narrative(self.list_exits()),
+            Ok(Command::Map) => Message::narrative(self.show_map()),
+            Ok(Command::AutoExits(show)) => {
+                self.show_exits = show;
+                Message::system(if show {
+                    "Exits will be listed when describing a room."
+                } else {
+                    "Exits will no longer be listed when describing a room."
+                })
+            }
+            Ok(Command::Save) => {
+                return Output::Save {
+                    game: self,
+                    messages: corrections,
+                };
+            }
+            Ok(Command::Help(topic)) => Message::system(help(topic)),
+            Ok(Command::Nop) => Message::narrative(String::new()),
+            Err(e @ CommandError::Word(WordError::Unknown(_))) => {
+                Message::parser_error(if suggestions.is_empty() {
+                    format!("{e}  {HELP_HINT}")
+                } else {
+                    format!("{e}  Did you mean {}?", english_list(&suggestions, "or"))
+                })
+            }
+            Err(e) => Message::parser_error(e.to_string()),
         };
-        Output::Continue { game: self, text }
+        let mut messages = corrections;
+        if !message.text.is_empty() {
+            messages.push(message);
+        }
+        Output::Continue {
+            game: self,
+            messages,
+        }
     }
 }
 
+impl GameEngine for Game {
+    fn handle_input(self, input: &str) -> Output<Self> {
+        let r = input.parse::<Command>();
+        self.dispatch(input.to_owned(), r, Vec::new(), false)
+    }
+
+    // The only question that walk asks is whether to quit.
+    fn handle_answer(self, answer: bool) -> Output<Self> {
+        if answer {
+            Output::goodbye("Be seeing you...")
+        } else {
+            Output::Continue {
+                game: self,
+                messages: vec![Message::system("Okay.")],
+            }
+        }
+    }
+
+    fn location(&self) -> Option<String> {
+        Some(self.location.name().to_owned())
+    }
+
+    fn status(&self) -> Option<String> {
+        Some(format!(
+            "Explored: {}/{}",
+            self.visited.len(),
+            Room::ALL.len()
+        ))
+    }
+
+    // The file is only remembered (and so autosaved to) once it has been
+    // written successfully.
+    fn save_game(&mut self) -> std::io::Result<()> {
+        let path = self
+            .save_file
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(save::DEFAULT_SAVE_FILE));
+        std::fs::write(&path, self.save())?;
+        self.save_file = Some(path);
+        Ok(())
+    }
+
+    // Only games that the player has already saved (or loaded from a file)
+    // are autosaved, so that an unrelated save file never gets clobbered.
+    fn autosave(&self) -> std::io::Result<()> {
+        match &self.save_file {
+            Some(path) => std::fs::write(path, self.save()),
+            None => Ok(()),
+        }
+    }
+}
+
+// The response when OOPS would turn the previous input into AGAIN or OOPS
+const OOPS_META_ERROR: &str = "You can't use OOPS to turn a command into AGAIN or OOPS.";
+
+// The result of `Game::target()`
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Target {
+    /// The verb should be applied to `entity`.  If the entity was inferred
+    /// rather than given by the player, `note` is a line stating the inference
+    /// to show before the result.
+    Found { entity: Entity, note: String },
+    /// No object was given, and nothing present qualifies
+    Nothing,
+    /// The command can't be carried out; the string is the message to show
+    Failed(String),
+}
+
+// Replace the first whitespace-separated token in `input` that equals `old`
+// with `new`
+fn replace_word(input: &str, old: &str, new: &str) -> String {
+    let mut replaced = false;
+    input
+        .split_whitespace()
+        .map(|t| {
+            if !replaced && t == old {
+                replaced = true;
+                new
+            } else {
+                t
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
 #[cfg(test)]
 mod tests;
@@ -1,3 +1,5 @@
+use super::vocab::Motion;
+
 #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub(crate) enum Room {
     NorthWest,
@@ -12,6 +14,79 @@ pub(crate) enum Room {
 }
 
 impl Room {
+    pub(crate) const ALL: [Room; 9] = [
+        Room::NorthWest,
+        Room::North,
+        Room::NorthEast,
+        Room::West,
+        Room::Center,
+        Room::East,
+        Room::SouthWest,
+        Room::South,
+        Room::SouthEast,
+    ];
+
+    /// Returns the room named after the given direction, if any, so that
+    /// players can refer to rooms by those directions
+    pub(crate) fn named_after(m: Motion) -> Option<Room> {
+        match m {
+            Motion::North => Some(Room::North),
+            Motion::NorthEast => Some(Room::NorthEast),
+            Motion::East => Some(Room::East),
+            Motion::SouthEast => Some(Room::SouthEast),
+            Motion::South => Some(Room::South),
+            Motion::SouthWest => Some(Room::SouthWest),
+            Motion::West => Some(Room::West),
+            Motion::NorthWest => Some(Room::NorthWest),
+            Motion::Up | Motion::Down | Motion::In | Motion::Out => None,
+        }
+    }
+
+    pub(crate) fn name(&self) -> &str {
+        match self {
+            Room::NorthWest => "North-West Room",
+            Room::North => "North Room",
+            Room::NorthEast => "North-East Room",
+            Room::West => "West Room",
+            Room::Center => "Center Room",
+            Room::East => "East Room",
+            Room::SouthWest => "South-West Room",
+            Room::South => "South Room",
+            Room::SouthEast => "South-East Room",
+        }
+    }
+
+    // Label for the room on the map
+    pub(crate) fn abbreviation(&self) -> &str {
+        match self {
+            Room::NorthWest => "NW",
+            Room::North => "N",
+            Room::NorthEast => "NE",
+            Room::West => "W",
+            Room::Center => "C",
+            Room::East => "E",
+            Room::SouthWest => "SW",
+            Room::South => "S",
+            Room::SouthEast => "SE",
+        }
+    }
+
+    // Position of the room on the map as `(x, y)`, with `y` increasing
+    // southwards
+    pub(crate) fn coords(&self) -> (i32, i32) {
+        match self {
+            Room::NorthWest => (0, 0),
+            Room::North => (1, 0),
+            Room::NorthEast => (2, 0),
+            Room::West => (0, 1),
+            Room::Center => (1, 1),
+            Room::East => (2, 1),
+            Room::SouthWest => (0, 2),
+            Room::South => (1, 2),
+            Room::SouthEast => (2, 2),
+        }
+    }
+
     pub(crate) fn long_description(&self) -> &str {
         match self {
             Room::NorthWest => {
@@ -21,7 +96,7 @@ impl Room {
             Room::NorthEast => "You are in the north-east room.",
             Room::West => "You are in the west room.  The lights are turned down low.",
             Room::Center => {
-                "You are in the center room.  Doors lead out in all cardinal directions."
+                "You are in the center room.  Doors lead out in all eight compass directions."
             }
             Room::East => "You are in the east room.  It gives off a bright & cheery air.",
             Room::SouthWest => "You are in the south-west room.",
@@ -44,3 +119,29 @@ impl Room {
         }
     }
 }
+
+/// How much detail to show when entering a room
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub(crate) enum Verbosity {
+    /// Always show the full description
+    Verbose,
+    /// Show the full description only on the first visit
+    Brief,
+    /// Show only the room's name
+    Superbrief,
+}
+
+impl Verbosity {
+    // Text displayed when the player switches to this mode
+    pub(crate) fn announce(&self) -> &str {
+        match self {
+            Verbosity::Verbose => "Maximum verbosity: rooms will always be described in full.",
+            Verbosity::Brief => {
+                "Brief descriptions: rooms will only be described in full the first time you enter them."
+            }
+            Verbosity::Superbrief => {
+                "Superbrief descriptions: only the names of rooms will be shown."
+            }
+        }
+    }
+}
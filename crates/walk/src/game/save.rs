@@ -0,0 +1,182 @@
+use super::Game;
+use super::help::HELP_HINT;
+use super::rooms::{Room, Verbosity};
+use super::vocab::Command;
+use advcore::{GameBuilder, Output, SavableEngine};
+use std::io;
+use std::path::Path;
+use thiserror::Error;
+
+/// The first line of every save file
+const SAVE_HEADER: &str = "walk save 1";
+
+/// Where the SAVE command writes to if the game wasn't loaded from a file
+pub(crate) const DEFAULT_SAVE_FILE: &str = "walk.sav";
+
+/// A [`GameBuilder`] that resumes a game loaded from a save file
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct Restored(Game);
+
+impl Restored {
+    /// Load a saved game from `path`.  Subsequent saves will be written back
+    /// to the same file.
+    pub(crate) fn load(path: &Path) -> Result<Restored, LoadError> {
+        let src = std::fs::read_to_string(path)?;
+        let mut game = Game::restore(&src)?;
+        game.save_file = Some(path.to_owned());
+        Ok(Restored(game))
+    }
+
+    /// Replace the game's random seed if `seed` is given
+    pub(crate) fn seed(mut self, seed: Option<u64>) -> Restored {
+        if seed.is_some() {
+            self.0.seed = seed;
+        }
+        self
+    }
+}
+
+impl From<Game> for Restored {
+    fn from(game: Game) -> Restored {
+        Restored(game)
+    }
+}
+
+impl GameBuilder for Restored {
+    type Engine = Game;
+
+    fn start(self) -> Output<Game> {
+        let mut game = self.0;
+        let text = format!("{}\n\n{HELP_HINT}", game.show_location(true));
+        Output::continue_with(game, text)
+    }
+}
+
+// The file that SAVE writes to is not part of the save data, so a game
+// restored this way has none.  That doesn't matter to a `SessionManager`, as
+// hosted games can't be saved.
+impl SavableEngine for Game {
+    type Error = RestoreError;
+
+    fn save(&self) -> String {
+        Game::save(self)
+    }
+
+    fn restore(data: &str) -> Result<Game, RestoreError> {
+        Game::restore(data)
+    }
+}
+
+impl Game {
+    /// Serialize the parts of the game state that persist between sessions.
+    ///
+    /// The format is line-based: a header line followed by one `key value`
+    /// line per field.
+    pub(crate) fn save(&self) -> String {
+        let mut visited = self.visited.iter().copied().collect::<Vec<_>>();
+        visited.sort_unstable();
+        let mut lines = vec![
+            String::from(SAVE_HEADER),
+            format!("location {:?}", self.location),
+        ];
+        if let Some(prev) = self.prev_location {
+            lines.push(format!("previous {prev:?}"));
+        }
+        let visited = visited
+            .into_iter()
+            .map(|r| format!("{r:?}"))
+            .collect::<Vec<_>>();
+        lines.push(format!("visited {}", visited.join(" ")));
+        lines.push(format!("verbosity {:?}", self.verbosity));
+        lines.push(format!(
+            "exits {}",
+            if self.show_exits { "on" } else { "off" }
+        ));
+        if let Some(seed) = self.seed {
+            lines.push(format!("seed {seed}"));
+        }
+        // Saved so that AGAIN and OOPS work after restoring
+        if let Some(input) = &self.last_input {
+            lines.push(format!("last {}", input.trim()));
+        }
+        let mut s = lines.join("\n");
+        s.push('\n');
+        s
+    }
+
+    /// Reconstruct a game from the output of [`Game::save()`]
+    pub(crate) fn restore(src: &str) -> Result<Game, RestoreError> {
+        let mut lines = src.lines().enumerate();
+        if lines.next().map(|(_, ln)| ln.trim_end()) != Some(SAVE_HEADER) {
+            return Err(RestoreError::Header);
+        }
+        let mut game = Game::new();
+        let mut location = None;
+        for (i, line) in lines {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let invalid = || RestoreError::Invalid {
+                lineno: i + 1,
+                line: line.to_owned(),
+            };
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            match key {
+                "location" => location = Some(parse_room(value).ok_or_else(invalid)?),
+                "previous" => game.prev_location = Some(parse_room(value).ok_or_else(invalid)?),
+                "visited" => {
+                    for v in value.split_whitespace() {
+                        game.visited.insert(parse_room(v).ok_or_else(invalid)?);
+                    }
+                }
+                "verbosity" => {
+                    game.verbosity = match value {
+                        "Verbose" => Verbosity::Verbose,
+                        "Brief" => Verbosity::Brief,
+                        "Superbrief" => Verbosity::Superbrief,
+                        _ => return Err(invalid()),
+                    };
+                }
+                "exits" => {
+                    game.show_exits = match value {
+                        "on" => true,
+                        "off" => false,
+                        _ => return Err(invalid()),
+                    };
+                }
+                "seed" => game.seed = Some(value.parse().map_err(|_| invalid())?),
+                "last" if !value.is_empty() => {
+                    game.last_error = value.parse::<Command>().err();
+                    game.last_input = Some(value.to_owned());
+                }
+                _ => return Err(invalid()),
+            }
+        }
+        game.location = location.ok_or(RestoreError::NoLocation)?;
+        game.visited.insert(game.location);
+        Ok(game)
+    }
+}
+
+fn parse_room(s: &str) -> Option<Room> {
+    Room::ALL.into_iter().find(|r| format!("{r:?}") == s)
+}
+
+#[derive(Debug, Error)]
+pub(crate) enum LoadError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Restore(#[from] RestoreError),
+}
+
+#[derive(Clone, Debug, Eq, Error, PartialEq)]
+pub(crate) enum RestoreError {
+    #[error("not a walk save file")]
+    Header,
+    #[error("invalid save data on line {lineno}: {line:?}")]
+    Invalid { lineno: usize, line: String },
+    #[error("save data does not specify a location")]
+    NoLocation,
+}
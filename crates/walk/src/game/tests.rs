@@ -1,9 +1,10 @@
+use super::save::RestoreError;
 use super::*;
-use advcore::Tester;
+use advcore::{MessageKind, SavableEngine, Tester};
 
 #[test]
 fn noback() {
-    let mut t = Tester::start(Builder);
+    let mut t = Tester::start(Builder::default());
     assert_eq!(t.game().location, Room::Center);
     t.input("BACK");
     t.assert_output("You weren't anywhere else before here.");
@@ -12,7 +13,7 @@ fn noback() {
 
 #[test]
 fn back() {
-    let mut t = Tester::start(Builder);
+    let mut t = Tester::start(Builder::default());
     t.input("NORTH");
     assert_eq!(t.game().location, Room::North);
     assert_eq!(t.game().prev_location, Some(Room::Center));
@@ -24,37 +25,89 @@ fn back() {
     assert_eq!(t.game().prev_location, Some(Room::Center));
 }
 
+#[test]
+fn status() {
+    let mut t = Tester::start(Builder::default());
+    assert_eq!(t.game().status().as_deref(), Some("Explored: 1/9"));
+    t.input("NORTH");
+    assert_eq!(t.game().status().as_deref(), Some("Explored: 2/9"));
+    t.input("BACK");
+    assert_eq!(t.game().status().as_deref(), Some("Explored: 2/9"));
+}
+
+#[test]
+fn motions() {
+    let mut t = Tester::start(Builder::default());
+    t.input("NE");
+    assert_eq!(t.game().location, Room::NorthEast);
+    t.input("SOUTH-WEST");
+    assert_eq!(t.game().location, Room::Center);
+    t.input("UP");
+    t.assert_output("There's no way to go up from here.");
+    t.input("GO OUT");
+    t.assert_output("There's no way to go out from here.");
+    t.input("WEST");
+    t.input("NORTHEAST");
+    t.assert_output("There's no way to go northeast from here.");
+    assert_eq!(t.game().location, Room::West);
+}
+
 #[test]
 fn room_descriptions() {
-    let mut t = Tester::start(Builder);
+    let mut t = Tester::start(Builder::default());
     t.assert_output(format!(
-        "{}\n\n{}",
+        "{}\n\n{}\n\nExits: north, northeast, east, southeast, south, southwest, west, northwest.\n\n{HELP_HINT}",
         Room::Center.long_description(),
         Entity::TicTacToe.describe()
     ));
     t.input("NORTH");
     t.assert_output(format!(
-        "{}\n\n{}",
+        "{}\n\n{}\n\nExits: east, south (to the center room), west.",
         Room::North.long_description(),
         Entity::Globe.describe()
     ));
     t.input("SOUTH");
     t.assert_output(format!(
-        "{}\n\n{}",
+        "{}\n\n{}\n\nExits: north (to the north room), northeast, east, southeast, south, southwest, west, northwest.",
         Room::Center.short_description(),
         Entity::TicTacToe.describe()
     ));
-    t.input("EXAMINE");
+    t.input("LOOK");
     t.assert_output(format!(
-        "{}\n\n{}",
+        "{}\n\n{}\n\nExits: north (to the north room), northeast, east, southeast, south, southwest, west, northwest.",
         Room::Center.long_description(),
         Entity::TicTacToe.describe()
     ));
 }
 
+#[test]
+fn exits() {
+    let mut t = Tester::start(Builder::default());
+    t.input("EXITS");
+    t.assert_output("Exits: north, northeast, east, southeast, south, southwest, west, northwest.");
+    t.input("EXITS OFF");
+    assert!(!t.game().show_exits);
+    t.input("WEST");
+    t.assert_output(format!(
+        "{}\n\n{}",
+        Room::West.long_description(),
+        Entity::Painting.describe()
+    ));
+    t.input("EXITS");
+    t.assert_output("Exits: north, east (to the center room), south.");
+    t.input("EXITS ON");
+    assert!(t.game().show_exits);
+    t.input("LOOK");
+    t.assert_output(format!(
+        "{}\n\n{}\n\nExits: north, east (to the center room), south.",
+        Room::West.long_description(),
+        Entity::Painting.describe()
+    ));
+}
+
 #[test]
 fn reading() {
-    let mut t = Tester::start(Builder);
+    let mut t = Tester::start(Builder::default());
     t.input("READ");
     t.assert_output("There's nothing here to read.");
     t.input("READ GRID");
@@ -66,7 +119,7 @@ fn reading() {
     t.input("SOUTH");
     t.input("WEST");
     t.input("READ");
-    t.assert_output("You sit and read for a while.");
+    t.assert_output("(the books)\nYou sit and read for a while.");
     t.input("READ GRID");
     t.assert_output("That isn't here.");
     t.input("READ BOOKS");
@@ -76,7 +129,9 @@ fn reading() {
     t.input("EAST");
     t.input("EAST");
     t.input("READ");
-    t.assert_output("The plans are all written in code.  You can't make heads or tails of them.");
+    t.assert_output(
+        "(the secret plans)\nThe plans are all written in code.  You can't make heads or tails of them.",
+    );
     t.input("READ GRID");
     t.assert_output("That isn't here.");
     t.input("READ BOOKS");
@@ -84,3 +139,450 @@ fn reading() {
     t.input("READ PLANS");
     t.assert_output("The plans are all written in code.  You can't make heads or tails of them.");
 }
+
+#[test]
+fn again() {
+    let mut t = Tester::start(Builder::default());
+    t.input("AGAIN");
+    t.assert_output("You haven't done anything yet.");
+    t.input("NORTH");
+    assert_eq!(t.game().location, Room::North);
+    t.input("BACK");
+    assert_eq!(t.game().location, Room::Center);
+    t.input("G");
+    assert_eq!(t.game().location, Room::North);
+    t.input("");
+    t.input("AGAIN");
+    assert_eq!(t.game().location, Room::Center);
+    t.input("EXAMINE GLOBE");
+    t.assert_output("That isn't here.");
+    t.input("AGAIN");
+    t.assert_output("That isn't here.");
+}
+
+#[test]
+fn oops() {
+    let mut t = Tester::start(Builder::default());
+    t.input("OOPS GRID");
+    t.assert_output("There was no word to replace!");
+    t.input("EXAMINE XYZZY");
+    t.assert_output("I don't know what \"XYZZY\" means.  (Type HELP for a list of commands.)");
+    t.input("OOPS GRID");
+    t.assert_output("X and O are locked in a dead heat.");
+    t.input("OOPS GRID");
+    t.assert_output("There was no word to replace!");
+    t.input("AGAIN");
+    t.assert_output("X and O are locked in a dead heat.");
+    t.input("EXAMINE PLUGH");
+    t.input("OOPS ZORKMID");
+    t.assert_output("I don't know what \"ZORKMID\" means.  (Type HELP for a list of commands.)");
+    t.input("OOPS GRID");
+    t.assert_output("X and O are locked in a dead heat.");
+}
+
+#[test]
+fn oops_into_meta_command() {
+    let mut t = Tester::start(Builder::default());
+    t.input("XYZZY");
+    t.input("OOPS AGAIN");
+    t.assert_output("You can't use OOPS to turn a command into AGAIN or OOPS.");
+    t.input("XYZZY GLOBE");
+    t.input("OOPS OOPS");
+    t.assert_output("You can't use OOPS to turn a command into AGAIN or OOPS.");
+}
+
+#[test]
+fn spelling() {
+    let mut t = Tester::start(Builder::default());
+    t.input("EXAMIEN GRIDD");
+    t.assert_output("(assuming EXAMINE)\n(assuming GRID)\nX and O are locked in a dead heat.");
+    t.input("AGAIN");
+    t.assert_output("X and O are locked in a dead heat.");
+    t.input("LOOK AT MOOK");
+    t.assert_output("I don't know what \"MOOK\" means.  Did you mean BOOK, LOOK, or MOON?");
+    t.input("OOPS BOOK");
+    t.assert_output("That isn't here.");
+    t.input("XYZZY");
+    t.assert_output("I don't know what \"XYZZY\" means.  (Type HELP for a list of commands.)");
+}
+
+#[test]
+fn misspelled_meta_commands() {
+    let mut t = Tester::start(Builder::default());
+    t.input("NORTH");
+    t.input("BACK");
+    t.input("AGIAN");
+    assert_eq!(t.last_messages()[0].text, "(assuming AGAIN)");
+    assert_eq!(t.game().location, Room::North);
+    t.input("EXAMINE ZORKMID");
+    t.input("OOSP GLOBE");
+    t.assert_output("(assuming OOPS)\nWait, that's not Earth.  Where am I?");
+    t.input("XYZZY");
+    t.input("OOPS AGIAN");
+    t.assert_output("(assuming AGAIN)\nYou can't use OOPS to turn a command into AGAIN or OOPS.");
+}
+
+#[test]
+fn message_kinds() {
+    fn kinds(t: &Tester<Game>) -> Vec<MessageKind> {
+        t.last_messages().iter().map(|m| m.kind).collect()
+    }
+
+    let mut t = Tester::start(Builder::default());
+    assert_eq!(kinds(&t), [MessageKind::Narrative]);
+    t.input("XYZZY");
+    assert_eq!(kinds(&t), [MessageKind::ParserError]);
+    t.input("GO");
+    assert_eq!(kinds(&t), [MessageKind::ParserError]);
+    t.input("EXAMIEN GRID");
+    assert_eq!(kinds(&t), [MessageKind::System, MessageKind::Narrative]);
+    t.input("BRIEF");
+    assert_eq!(kinds(&t), [MessageKind::System]);
+    t.input("");
+    assert_eq!(kinds(&t), []);
+}
+
+#[test]
+fn quit() {
+    let mut t = Tester::start(Builder::default());
+    t.input("QUIT");
+    t.assert_output("Are you sure you want to quit?");
+    t.input("maybe");
+    t.assert_output("Please answer yes or no.");
+    t.input("n");
+    t.assert_output("Okay.");
+    t.input("QUIT");
+    t.input("YES");
+    t.assert_output("Be seeing you...");
+    assert!(t.done());
+}
+
+#[test]
+fn implicit_object() {
+    let mut game = Game::new();
+    game.placements.place(Entity::SecretPlans, Room::Center);
+    let mut t = Tester::from(game);
+    t.input("READ");
+    t.assert_output(
+        "(the secret plans)\nThe plans are all written in code.  You can't make heads or tails of them.",
+    );
+    t.input("EXAMINE");
+    t.assert_output("What do you want to examine?");
+    t.input("EXAMINE GRID");
+    t.assert_output("X and O are locked in a dead heat.");
+    t.input("NORTH");
+    t.input("EXAMINE");
+    t.assert_output("(the globe)\nWait, that's not Earth.  Where am I?");
+    t.input("READ");
+    t.assert_output("There's nothing here to read.");
+}
+
+#[test]
+fn help_command() {
+    let mut t = Tester::start(Builder::default());
+    t.input("HELP");
+    t.assert_output(concat!(
+        "I understand the following commands:\n",
+        "\n",
+        "NORTH, N, NE, NORTHEAST, NORTH-EAST, EAST, E, SE, SOUTHEAST, SOUTH-EAST, SOUTH, S, SW, SOUTHWEST, SOUTH-WEST, WEST, W, NW, NORTHWEST, NORTH-WEST, UP, U, DOWN, D, IN, ENTER, INSIDE, OUT, OUTSIDE — Move in the given direction\n",
+        "GO — Move in the given direction, or walk to a room you've already visited by the shortest route\n",
+        "BACK, RETURN, RETREAT — Return to the previous room\n",
+        "LOOK — Describe the current room in full, or look closely at an object\n",
+        "EXAMINE, DESCRIBE — Look closely at an object\n",
+        "READ — Read a readable object\n",
+        "AGAIN, G — Repeat your last command\n",
+        "OOPS — Type OOPS followed by a word to use it in place of the word I didn't understand in your last command\n",
+        "VERBOSE — Always describe rooms in full\n",
+        "BRIEF — Only describe rooms in full the first time you enter them (the default)\n",
+        "SUPERBRIEF — Only show the names of rooms, not their descriptions or contents\n",
+        "EXITS — List the ways out of the current room, or turn listing them in room descriptions on or off\n",
+        "MAP — Show a map of the rooms you've visited, with your location marked\n",
+        "SAVE — Save your progress so that you can pick up where you left off\n",
+        "HELP — List the commands, or explain how to use one\n",
+        "EXIT, QUIT — Quit the game\n",
+        "\n",
+        "Type HELP followed by a command to see how to use it.",
+    ));
+    t.input("HELP RETREAT");
+    t.assert_output(concat!(
+        "BACK, RETURN, RETREAT — Return to the previous room\n",
+        "\n",
+        "Usage:\n",
+        "    BACK",
+    ));
+    t.input("HELP LOOK");
+    t.assert_output(concat!(
+        "LOOK — Describe the current room in full, or look closely at an object\n",
+        "\n",
+        "Usage:\n",
+        "    LOOK\n",
+        "    LOOK ROOM\n",
+        "    LOOK ⟨object⟩\n",
+        "    LOOK AT ROOM\n",
+        "    LOOK AT ⟨object⟩",
+    ));
+    t.input("HELP OOPS");
+    t.assert_output(concat!(
+        "OOPS — Type OOPS followed by a word to use it in place of the word I didn't understand in your last command\n",
+        "\n",
+        "Usage:\n",
+        "    OOPS ⟨word⟩",
+    ));
+    t.input("HELP GLOBE");
+    t.assert_output("That isn't a command.  Type HELP for a list of commands.");
+}
+
+#[test]
+fn verbosity() {
+    let mut t = Tester::start(Builder::default());
+    t.input("EXITS OFF");
+    t.input("NORTH");
+    t.input("SOUTH");
+    t.assert_output(format!(
+        "{}\n\n{}",
+        Room::Center.short_description(),
+        Entity::TicTacToe.describe()
+    ));
+    t.input("VERBOSE");
+    assert_eq!(t.game().verbosity, Verbosity::Verbose);
+    t.input("NORTH");
+    t.assert_output(format!(
+        "{}\n\n{}",
+        Room::North.long_description(),
+        Entity::Globe.describe()
+    ));
+    t.input("SUPERBRIEF");
+    assert_eq!(t.game().verbosity, Verbosity::Superbrief);
+    t.input("SOUTH");
+    t.assert_output("Center Room");
+    t.input("WEST");
+    t.assert_output("West Room");
+    t.input("LOOK");
+    t.assert_output(format!(
+        "{}\n\n{}",
+        Room::West.long_description(),
+        Entity::Painting.describe()
+    ));
+    t.input("BRIEF");
+    assert_eq!(t.game().verbosity, Verbosity::Brief);
+    t.input("EAST");
+    t.assert_output(format!(
+        "{}\n\n{}",
+        Room::Center.short_description(),
+        Entity::TicTacToe.describe()
+    ));
+}
+
+#[test]
+fn room_contents() {
+    let mut game = Game::new();
+    game.placements.place(Entity::SecretPlans, Room::Center);
+    game.placements.place(Entity::Mural, Room::Center);
+    game.placements.place(Entity::Globe, Room::Center);
+    game.show_exits = false;
+    let mut t = Tester::from(game);
+    t.input("LOOK");
+    t.assert_output(format!(
+        "{}\n\n{}\n\nYou can also see the tic-tac-toe grid, the mural, and the secret plans here.",
+        Room::Center.long_description(),
+        Entity::Globe.describe()
+    ));
+    t.input("EAST");
+    t.assert_output(Room::East.long_description());
+    t.input("EXAMINE MURAL");
+    t.assert_output("That isn't here.");
+}
+
+#[test]
+fn go_to() {
+    let mut t = Tester::start(Builder::default());
+    t.input("EXITS OFF");
+    t.input("GO TO CENTER");
+    t.assert_output("You're already there.");
+    t.input("GO TO NORTH-EAST ROOM");
+    t.assert_output("You don't know how to get there.");
+    t.input("NORTH");
+    t.input("EAST");
+    t.input("SOUTH");
+    t.input("SOUTH");
+    t.input("WEST");
+    assert_eq!(t.game().location, Room::South);
+    t.input("GO TO NORTHEAST");
+    t.assert_output(format!(
+        "{}\n\n{}\n\n{}\n\n{}",
+        Room::Center.short_description(),
+        Entity::TicTacToe.describe(),
+        Room::NorthEast.short_description(),
+        Entity::Cushions.describe(),
+    ));
+    assert_eq!(t.game().location, Room::NorthEast);
+    assert_eq!(t.game().prev_location, Some(Room::Center));
+    t.input("GO TO WEST");
+    t.assert_output("You don't know how to get there.");
+    t.input("GO TO SOUTH-EAST");
+    assert_eq!(t.game().location, Room::SouthEast);
+}
+
+#[test]
+fn map() {
+    let mut t = Tester::start(Builder::default());
+    t.input("MAP");
+    t.assert_output(concat!("+-----+\n", "| *C* |\n", "+-----+"));
+    t.input("NORTH");
+    t.input("WEST");
+    t.input("SOUTH");
+    t.input("MAP");
+    t.assert_output(concat!(
+        "+-----+   +-----+\n",
+        "| NW  |---|  N  |\n",
+        "+-----+   +-----+\n",
+        "   |    \\    |\n",
+        "+-----+   +-----+\n",
+        "| *W* |---|  C  |\n",
+        "+-----+   +-----+",
+    ));
+}
+
+#[test]
+fn export_world_graph() {
+    let dot = world_graph().to_dot();
+    assert!(dot.contains("    NorthWest [label=\"North-West Room\\nthe banquet\"];\n"));
+    assert!(dot.contains("    NorthWest -> North [label=\"east\"];\n"));
+    assert!(!dot.contains("color=red"));
+    let mermaid = world_graph().to_mermaid();
+    assert!(mermaid.contains("    Center[\"Center Room<br/>the tic-tac-toe grid\"]\n"));
+    assert!(mermaid.contains("    Center -->|\"north\"| North\n"));
+    assert!(!mermaid.contains("linkStyle"));
+}
+
+#[test]
+fn save_and_restore() {
+    let mut t = Tester::start(Builder::default().seed(Some(42)));
+    t.input("NORTH");
+    t.input("WEST");
+    t.input("SUPERBRIEF");
+    t.input("EXITS OFF");
+    let saved = t.game().save();
+    assert_eq!(
+        saved,
+        concat!(
+            "walk save 1\n",
+            "location NorthWest\n",
+            "previous North\n",
+            "visited NorthWest North Center\n",
+            "verbosity Superbrief\n",
+            "exits off\n",
+            "seed 42\n",
+            "last EXITS OFF\n",
+        )
+    );
+    let game = Game::restore(&saved).unwrap();
+    assert_eq!(game.location, Room::NorthWest);
+    assert_eq!(game.prev_location, Some(Room::North));
+    assert_eq!(game.visited, t.game().visited);
+    assert_eq!(game.verbosity, Verbosity::Superbrief);
+    assert!(!game.show_exits);
+    assert_eq!(game.seed, Some(42));
+    let mut t = Tester::start(Restored::from(game));
+    t.assert_output(format!(
+        "{}\n\n{}\n\n{HELP_HINT}",
+        Room::NorthWest.long_description(),
+        Entity::Banquet.describe()
+    ));
+    t.input("BACK");
+    assert_eq!(t.game().location, Room::North);
+}
+
+#[test]
+fn restore_last_input() {
+    let mut t = Tester::start(Builder::default());
+    t.input("NORTH");
+    t.input("EXAMINE ZORKMID");
+    let game = <Game as SavableEngine>::restore(&t.game().save()).unwrap();
+    assert_eq!(game.save_file, None);
+    let mut t = Tester::from(game);
+    t.input("OOPS GLOBE");
+    t.assert_output("Wait, that's not Earth.  Where am I?");
+    let game = <Game as SavableEngine>::restore(&t.game().save()).unwrap();
+    let mut t = Tester::from(game);
+    t.input("AGAIN");
+    t.assert_output("Wait, that's not Earth.  Where am I?");
+}
+
+#[test]
+fn restore_multiline_last_input() {
+    let mut t = Tester::start(Builder::default());
+    t.input("NORTH");
+    t.input("LOOK\nlocation South");
+    let saved = t.game().save();
+    assert!(saved.ends_with("\nlast LOOK location South\n"));
+    let game = <Game as SavableEngine>::restore(&saved).unwrap();
+    assert_eq!(&game, t.game());
+    let mut t = Tester::from(game);
+    t.input("EXAMINE\nZORKMID");
+    let game = <Game as SavableEngine>::restore(&t.game().save()).unwrap();
+    let mut t = Tester::from(game);
+    t.input("OOPS GLOBE");
+    t.assert_output("Wait, that's not Earth.  Where am I?");
+}
+
+#[test]
+fn restore_errors() {
+    assert_eq!(
+        Game::restore("location Center\n"),
+        Err(RestoreError::Header)
+    );
+    assert_eq!(
+        Game::restore("walk save 1\nlocation Attic\n"),
+        Err(RestoreError::Invalid {
+            lineno: 2,
+            line: String::from("location Attic"),
+        })
+    );
+    assert_eq!(
+        Game::restore("walk save 1\nverbosity Brief\n"),
+        Err(RestoreError::NoLocation)
+    );
+}
+
+#[test]
+fn save_command() {
+    let path = std::env::temp_dir().join(format!("walk-save-test-{}.sav", std::process::id()));
+    let mut t = Tester::start(Builder::default());
+    t.input("SOUTH");
+    let mut game = t.game().clone();
+    game.save_file = Some(path.clone());
+    let mut t = Tester::from(game);
+    t.input("SAVE");
+    t.assert_output("Game saved.");
+    let t = Tester::start(Restored::load(&path).unwrap());
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(t.game().location, Room::South);
+    assert_eq!(t.game().save_file, Some(path));
+}
+
+#[test]
+fn refused_save_command() {
+    // A host that doesn't allow saving never calls save_game(), so the game
+    // must not think that it has a save file to autosave to.
+    let Output::Save { game, .. } = Game::new().handle_input("SAVE") else {
+        panic!("SAVE did not ask to save the game");
+    };
+    assert_eq!(game.save_file, None);
+}
+
+#[test]
+fn autosave() {
+    let path = std::env::temp_dir().join(format!("walk-autosave-test-{}.sav", std::process::id()));
+    let mut t = Tester::start(Builder::default());
+    t.input("EAST");
+    let mut game = t.game().clone();
+    // A game that has never been saved isn't autosaved.
+    assert_eq!(game.save_file, None);
+    game.autosave().unwrap();
+    game.save_file = Some(path.clone());
+    game.autosave().unwrap();
+    let t = Tester::start(Restored::load(&path).unwrap());
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(t.game().location, Room::East);
+}
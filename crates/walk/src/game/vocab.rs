@@ -1,34 +1,89 @@
+use super::data::{GRAMMAR, MIN_ABBREVIATION_LENGTH, VOCABULARY};
 use super::entities::Entity;
+use super::rooms::{Room, Verbosity};
+use advcore::{Abbreviations, Expansion, english_list};
+use std::sync::LazyLock;
 use thiserror::Error;
 use unicase::UniCase;
 
+static ABBREVIATIONS: LazyLock<Abbreviations<'static, Word>> = LazyLock::new(|| {
+    Abbreviations::new(VOCABULARY.entries().map(|(k, &v)| (k.into_inner(), v)))
+        .min_length(MIN_ABBREVIATION_LENGTH)
+});
+
 #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub(crate) enum Word {
     Motion(Motion),
     Action(Action),
     Entity(Entity),
+    Place(Room),
     Look,
+    Go,
     At,
+    To,
     Room,
+    On,
+    Off,
+    Oops,
+}
+
+impl Word {
+    fn is_preposition(self) -> bool {
+        matches!(self, Word::At | Word::To)
+    }
 }
 
 impl std::str::FromStr for Word {
     type Err = WordError;
 
     fn from_str(s: &str) -> Result<Word, WordError> {
-        super::data::VOCABULARY
-            .get(&UniCase::ascii(s))
-            .copied()
-            .ok_or_else(|| WordError(s.to_owned()))
+        if let Some(&word) = VOCABULARY.get(&UniCase::ascii(s)) {
+            return Ok(word);
+        }
+        match ABBREVIATIONS.expand(s) {
+            Expansion::Unique(word) => Ok(word),
+            Expansion::Ambiguous(words) => Err(WordError::Ambiguous {
+                word: s.to_owned(),
+                completions: words,
+            }),
+            Expansion::Unknown => Err(WordError::Unknown(s.to_owned())),
+        }
     }
 }
 
 #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub(crate) enum Motion {
     North,
+    NorthEast,
     East,
+    SouthEast,
     South,
+    SouthWest,
     West,
+    NorthWest,
+    Up,
+    Down,
+    In,
+    Out,
+}
+
+impl Motion {
+    pub(crate) fn name(&self) -> &str {
+        match self {
+            Motion::North => "north",
+            Motion::NorthEast => "northeast",
+            Motion::East => "east",
+            Motion::SouthEast => "southeast",
+            Motion::South => "south",
+            Motion::SouthWest => "southwest",
+            Motion::West => "west",
+            Motion::NorthWest => "northwest",
+            Motion::Up => "up",
+            Motion::Down => "down",
+            Motion::In => "in",
+            Motion::Out => "out",
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
@@ -36,26 +91,73 @@ pub(crate) enum Action {
     Examine,
     Read,
     Back,
+    Again,
+    Verbose,
+    Brief,
+    Superbrief,
+    Exits,
+    Map,
+    Save,
+    Help,
     Quit,
 }
 
-#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub(crate) enum Command {
     Motion(Motion),
+    /// Walk to the given room by the shortest known route
+    GoTo(Room),
+    /// Describe the current room in full
+    Look,
     Examine(Option<Entity>),
     Read(Option<Entity>),
     Back,
+    Again,
+    /// Replace the unknown word in the previous input with the given word and
+    /// try again
+    Oops(String),
+    SetVerbosity(Verbosity),
+    /// List the exits from the current room
+    Exits,
+    /// Set whether to list exits when describing a room
+    AutoExits(bool),
+    /// Draw a map of the visited rooms
+    Map,
+    /// Write the game state to the save file
+    Save,
+    /// Show help in general or for the given verb
+    Help(Option<Word>),
     Quit,
     Nop,
 }
 
-impl From<Action> for Command {
-    fn from(value: Action) -> Command {
-        match value {
-            Action::Examine => Command::Examine(None),
-            Action::Read => Command::Read(None),
-            Action::Back => Command::Back,
-            Action::Quit => Command::Quit,
+impl Command {
+    /// Describe the command as a verb phrase, e.g., "examine the globe"
+    pub(crate) fn describe(&self) -> String {
+        match self {
+            Command::Motion(m) => format!("go {}", m.name()),
+            Command::GoTo(room) => format!("go to the {}", room.name().to_ascii_lowercase()),
+            Command::Look => String::from("look around"),
+            Command::Examine(None) => String::from("examine"),
+            Command::Examine(Some(en)) => format!("examine {}", en.name()),
+            Command::Read(None) => String::from("read"),
+            Command::Read(Some(en)) => format!("read {}", en.name()),
+            Command::Back => String::from("go back"),
+            Command::Again => String::from("repeat your last command"),
+            Command::Oops(word) => format!("use {word} instead"),
+            Command::SetVerbosity(Verbosity::Verbose) => String::from("use verbose descriptions"),
+            Command::SetVerbosity(Verbosity::Brief) => String::from("use brief descriptions"),
+            Command::SetVerbosity(Verbosity::Superbrief) => {
+                String::from("use superbrief descriptions")
+            }
+            Command::Exits => String::from("list the exits"),
+            Command::AutoExits(true) => String::from("turn on exit listing"),
+            Command::AutoExits(false) => String::from("turn off exit listing"),
+            Command::Map => String::from("see the map"),
+            Command::Save => String::from("save"),
+            Command::Help(_) => String::from("get help"),
+            Command::Quit => String::from("quit"),
+            Command::Nop => String::from("do nothing"),
         }
     }
 }
@@ -64,38 +166,191 @@ impl std::str::FromStr for Command {
     type Err = CommandError;
 
     fn from_str(s: &str) -> Result<Command, CommandError> {
-        let words = s
-            .split_whitespace()
-            .map(str::parse::<Word>)
+        let tokens = s.split_whitespace().collect::<Vec<_>>();
+        // The argument to OOPS is a replacement for a word that we didn't
+        // know, so don't try to parse it here.
+        if let Some((first, rest)) = tokens.split_first() {
+            if first.parse::<Word>() == Ok(Word::Oops) {
+                return match rest {
+                    [word] => Ok(Command::Oops((*word).to_owned())),
+                    [] => Err(CommandError::MissingObject {
+                        verb: "use instead",
+                    }),
+                    [word, ..] => Err(CommandError::ExtraWords {
+                        understood: Command::Oops((*word).to_owned()).describe(),
+                    }),
+                };
+            }
+        }
+        let words = tokens
+            .iter()
+            .map(|t| t.parse::<Word>())
             .collect::<Result<Vec<_>, _>>()?;
-        match words.as_slice() {
-            [Word::Motion(m)] => Ok(Command::Motion(*m)),
-            [Word::Action(act)] => Ok(Command::from(*act)),
-            [Word::Action(Action::Examine) | Word::Look, Word::Room] => Ok(Command::Examine(None)),
-            [Word::Action(Action::Examine) | Word::Look, Word::Entity(en)] => {
-                Ok(Command::Examine(Some(*en)))
+        if words.is_empty() {
+            return Ok(Command::Nop);
+        }
+        // For each rule, determine how many leading words it matches.  A rule
+        // that matches all of the words is the parse; otherwise, the rules
+        // that got the farthest determine the error.
+        let mut matches = Vec::with_capacity(GRAMMAR.len());
+        for rule in &GRAMMAR {
+            let (len, caps) = rule.match_prefix(&words);
+            if len == words.len() && len == rule.pattern.len() {
+                if let Some(cmd) = (rule.build)(caps) {
+                    return Ok(cmd);
+                }
+            }
+            matches.push((rule, len, caps));
+        }
+        let farthest = matches.iter().map(|&(_, len, _)| len).max().unwrap_or(0);
+        if farthest == 0 {
+            return Err(CommandError::UnknownVerb);
+        }
+        let mut understood = None;
+        for (rule, len, caps) in matches {
+            if len != farthest {
+                continue;
+            }
+            if len < rule.pattern.len() {
+                // The rule wanted something else next.
+                return Err(match rule.pattern[len - 1] {
+                    Slot::Word(w) if w.is_preposition() => CommandError::MissingPrepObject {
+                        prep: tokens[len - 1].to_ascii_uppercase(),
+                        verb: rule.verb,
+                    },
+                    _ => CommandError::MissingObject { verb: rule.verb },
+                });
+            } else if understood.is_none() {
+                understood = (rule.build)(caps);
+            }
+        }
+        Err(CommandError::ExtraWords {
+            understood: understood
+                .as_ref()
+                .map_or_else(String::new, Command::describe),
+        })
+    }
+}
+
+/// A verb that acts on an entity, which can be left implicit if there is only
+/// one entity present that the verb applies to
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub(crate) enum Verb {
+    Examine,
+    Read,
+}
+
+impl Verb {
+    pub(crate) fn name(&self) -> &str {
+        match self {
+            Verb::Examine => "examine",
+            Verb::Read => "read",
+        }
+    }
+
+    /// Returns whether the verb can meaningfully be applied to the entity
+    pub(crate) fn applies_to(&self, en: Entity) -> bool {
+        match self {
+            Verb::Examine => true,
+            Verb::Read => en.read().is_some(),
+        }
+    }
+}
+
+/// An element of a grammar rule
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub(crate) enum Slot {
+    /// The given word
+    Word(Word),
+    /// Any direction of motion
+    Motion,
+    /// Any entity
+    Entity,
+    /// Any room
+    Place,
+    /// Any word at all
+    Any,
+}
+
+/// The values matched by the `Motion`, `Entity`, `Place`, and `Any` slots of a grammar rule
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub(crate) struct Captures {
+    pub(crate) motion: Option<Motion>,
+    pub(crate) entity: Option<Entity>,
+    pub(crate) room: Option<Room>,
+    pub(crate) word: Option<Word>,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Rule {
+    /// How to refer to the action performed by the rule when asking the player
+    /// for more information, e.g., "What do you want to {verb}?"
+    pub(crate) verb: &'static str,
+    pub(crate) pattern: &'static [Slot],
+    pub(crate) build: fn(Captures) -> Option<Command>,
+}
+
+impl Rule {
+    // Returns the number of leading `words` that match the rule's pattern
+    // along with the values captured from them
+    fn match_prefix(&self, words: &[Word]) -> (usize, Captures) {
+        let mut caps = Captures::default();
+        let mut len = 0;
+        for (slot, word) in std::iter::zip(self.pattern, words) {
+            match (slot, word) {
+                (Slot::Word(w), _) if w == word => (),
+                (Slot::Motion, Word::Motion(m)) => caps.motion = Some(*m),
+                (Slot::Entity, Word::Entity(en)) => caps.entity = Some(*en),
+                (Slot::Place, Word::Place(room)) => caps.room = Some(*room),
+                (Slot::Place, Word::Motion(m)) if Room::named_after(*m).is_some() => {
+                    caps.room = Room::named_after(*m);
+                }
+                (Slot::Any, _) => caps.word = Some(*word),
+                _ => break,
             }
-            [Word::Look] => Ok(Command::Examine(None)),
-            [Word::Look, Word::At, Word::Room] => Ok(Command::Examine(None)),
-            [Word::Look, Word::At, Word::Entity(en)] => Ok(Command::Examine(Some(*en))),
-            [Word::Action(Action::Read), Word::Entity(en)] => Ok(Command::Read(Some(*en))),
-            [] => Ok(Command::Nop),
-            _ => Err(CommandError::BadGrammar),
+            len += 1;
         }
+        (len, caps)
     }
 }
 
 #[derive(Clone, Debug, Eq, Error, PartialEq)]
 pub(crate) enum CommandError {
-    #[error("I know what those words mean, but that sentence makes no sense.")]
-    BadGrammar,
+    #[error("That's not a verb I recognize.")]
+    UnknownVerb,
+    #[error("What do you want to {verb}?")]
+    MissingObject { verb: &'static str },
+    #[error("You used '{prep}' but didn't say what to {verb}.")]
+    MissingPrepObject { prep: String, verb: &'static str },
+    #[error("I only understood you as far as wanting to {understood}.")]
+    ExtraWords { understood: String },
     #[error(transparent)]
     Word(#[from] WordError),
 }
 
 #[derive(Clone, Debug, Eq, Error, PartialEq)]
-#[error("I don't know what {0:?} means.")]
-pub(crate) struct WordError(String);
+pub(crate) enum WordError {
+    #[error("I don't know what {0:?} means.")]
+    Unknown(String),
+    #[error(
+        "I don't know whether {word:?} means {}.",
+        english_list(completions, "or")
+    )]
+    Ambiguous {
+        word: String,
+        completions: Vec<&'static str>,
+    },
+}
+
+impl WordError {
+    /// Returns the unknown or ambiguous word as it appeared in the input
+    pub(crate) fn word(&self) -> &str {
+        match self {
+            WordError::Unknown(word) => word,
+            WordError::Ambiguous { word, .. } => word,
+        }
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -104,19 +359,126 @@ mod tests {
 
     #[rstest]
     #[case("EXAMINE", Ok(Command::Examine(None)))]
-    #[case("EXAMINE ROOM", Ok(Command::Examine(None)))]
-    #[case("LOOK", Ok(Command::Examine(None)))]
-    #[case("LOOK ROOM", Ok(Command::Examine(None)))]
-    #[case("LOOK AT ROOM", Ok(Command::Examine(None)))]
+    #[case("EXAMINE ROOM", Ok(Command::Look))]
+    #[case("LOOK", Ok(Command::Look))]
+    #[case("LOOK ROOM", Ok(Command::Look))]
+    #[case("LOOK AT ROOM", Ok(Command::Look))]
     #[case("LOOK BOOK", Ok(Command::Examine(Some(Entity::Books))))]
     #[case("LOOK AT BOOK", Ok(Command::Examine(Some(Entity::Books))))]
     #[case("LOOK @ BOOK", Ok(Command::Examine(Some(Entity::Books))))]
-    #[case("LOOK AT", Err(CommandError::BadGrammar))]
-    #[case("EXAMINE AT", Err(CommandError::BadGrammar))]
-    #[case("EXAMINE AT ROOM", Err(CommandError::BadGrammar))]
-    #[case("EXAMINE AT BOOK", Err(CommandError::BadGrammar))]
+    #[case("LOOK AT", Err(CommandError::MissingPrepObject {
+        prep: String::from("AT"),
+        verb: "look at",
+    }))]
+    #[case("look @", Err(CommandError::MissingPrepObject {
+        prep: String::from("@"),
+        verb: "look at",
+    }))]
+    #[case("LOOK AT NORTH", Err(CommandError::MissingPrepObject {
+        prep: String::from("AT"),
+        verb: "look at",
+    }))]
+    #[case("EXAMINE AT", Err(CommandError::MissingObject { verb: "examine" }))]
+    #[case("EXAMINE AT ROOM", Err(CommandError::MissingObject { verb: "examine" }))]
+    #[case("EXAMINE AT BOOK", Err(CommandError::MissingObject { verb: "examine" }))]
+    #[case("READ NORTH", Err(CommandError::MissingObject { verb: "read" }))]
+    #[case("READ ROOM", Err(CommandError::MissingObject { verb: "read" }))]
+    #[case("EXAMINE GLOBE BOOK", Err(CommandError::ExtraWords {
+        understood: String::from("examine the globe"),
+    }))]
+    #[case("LOOK AT ROOM NORTH", Err(CommandError::ExtraWords {
+        understood: String::from("look around"),
+    }))]
+    #[case("NORTH SOUTH", Err(CommandError::ExtraWords {
+        understood: String::from("go north"),
+    }))]
+    #[case(
+        "QUIT NOW",
+        Err(CommandError::Word(WordError::Unknown(String::from("NOW"))))
+    )]
+    #[case("QUIT BACK", Err(CommandError::ExtraWords {
+        understood: String::from("quit"),
+    }))]
+    #[case("GLOBE", Err(CommandError::UnknownVerb))]
+    #[case("AT GLOBE", Err(CommandError::UnknownVerb))]
+    #[case("AGAIN", Ok(Command::Again))]
+    #[case("VERBOSE", Ok(Command::SetVerbosity(Verbosity::Verbose)))]
+    #[case("brief", Ok(Command::SetVerbosity(Verbosity::Brief)))]
+    #[case("SUPER", Ok(Command::SetVerbosity(Verbosity::Superbrief)))]
+    #[case("GO NORTH", Ok(Command::Motion(Motion::North)))]
+    #[case("NE", Ok(Command::Motion(Motion::NorthEast)))]
+    #[case("go north-west", Ok(Command::Motion(Motion::NorthWest)))]
+    #[case("U", Ok(Command::Motion(Motion::Up)))]
+    #[case("GO INSIDE", Ok(Command::Motion(Motion::In)))]
+    #[case("GO TO UP", Err(CommandError::MissingPrepObject {
+        prep: String::from("TO"),
+        verb: "go to",
+    }))]
+    #[case("GO TO NORTH", Ok(Command::GoTo(Room::North)))]
+    #[case("GO TO NORTH ROOM", Ok(Command::GoTo(Room::North)))]
+    #[case("go to south-west room", Ok(Command::GoTo(Room::SouthWest)))]
+    #[case("GO TO CENTRE", Ok(Command::GoTo(Room::Center)))]
+    #[case("GO TO", Err(CommandError::MissingPrepObject {
+        prep: String::from("TO"),
+        verb: "go to",
+    }))]
+    #[case("GO TO GLOBE", Err(CommandError::MissingPrepObject {
+        prep: String::from("TO"),
+        verb: "go to",
+    }))]
+    #[case("GO", Err(CommandError::MissingObject { verb: "go" }))]
+    #[case("EXITS", Ok(Command::Exits))]
+    #[case("EXITS ON", Ok(Command::AutoExits(true)))]
+    #[case("exits off", Ok(Command::AutoExits(false)))]
+    #[case("EXITS GLOBE", Err(CommandError::MissingObject { verb: "list exits" }))]
+    #[case("MAP", Ok(Command::Map))]
+    #[case("SAVE", Ok(Command::Save))]
+    #[case("HELP", Ok(Command::Help(None)))]
+    #[case("HELP RETURN", Ok(Command::Help(Some(Word::Action(Action::Back)))))]
+    #[case("HELP N", Ok(Command::Help(Some(Word::Motion(Motion::North)))))]
+    #[case("HELP LOOK AT", Err(CommandError::ExtraWords {
+        understood: String::from("get help"),
+    }))]
+    #[case("g", Ok(Command::Again))]
+    #[case("OOPS GLOBE", Ok(Command::Oops(String::from("GLOBE"))))]
+    #[case("oops globb", Ok(Command::Oops(String::from("globb"))))]
+    #[case("OOPS", Err(CommandError::MissingObject { verb: "use instead" }))]
+    #[case("OOPS GLOBE BOOK", Err(CommandError::ExtraWords {
+        understood: String::from("use GLOBE instead"),
+    }))]
+    #[case("LOOK OOPS", Err(CommandError::MissingObject { verb: "look at" }))]
+    #[case("EXAM GLO", Ok(Command::Examine(Some(Entity::Globe))))]
+    #[case("desc", Ok(Command::Examine(None)))]
+    #[case("LOOK AT PENG", Ok(Command::Examine(Some(Entity::PenguinPhoto))))]
+    #[case("READ BOO", Ok(Command::Read(Some(Entity::Books))))]
+    #[case("RET", Ok(Command::Back))]
+    #[case("EA", Err(CommandError::Word(WordError::Unknown(String::from("EA")))))]
+    #[case("EAS", Err(CommandError::Word(WordError::Ambiguous {
+        word: String::from("EAS"),
+        completions: vec!["EASEL", "EAST"],
+    })))]
+    #[case(
+        "EXAMINES",
+        Err(CommandError::Word(WordError::Unknown(String::from("EXAMINES"))))
+    )]
     fn parse_command(#[case] s: &str, #[case] r: Result<Command, CommandError>) {
         let got = s.parse::<Command>();
         assert_eq!(got, r, "wrong parse for {s:?}");
     }
+
+    #[rstest]
+    #[case("READ AT", "What do you want to read?")]
+    #[case("LOOK AT", "You used 'AT' but didn't say what to look at.")]
+    #[case(
+        "EXAMINE GLOBE BOOK",
+        "I only understood you as far as wanting to examine the globe."
+    )]
+    #[case("GLOBE", "That's not a verb I recognize.")]
+    #[case("XYZZY", "I don't know what \"XYZZY\" means.")]
+    fn command_error_message(#[case] s: &str, #[case] msg: &str) {
+        let Err(e) = s.parse::<Command>() else {
+            panic!("{s:?} parsed successfully");
+        };
+        assert_eq!(e.to_string(), msg);
+    }
 }
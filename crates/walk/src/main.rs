@@ -1,6 +1,425 @@
 mod game;
-use advcore::{ReadlineInterfaceBuilder, io_exit, run_game};
+#[cfg(feature = "tui")]
+use advcore::TuiInterfaceBuilder;
+use advcore::{
+    BasicInterfaceBuilder, GameBuilder, GraphFormat, InterfaceBuilder, JsonLinesInterfaceBuilder,
+    ReadlineInterfaceBuilder, SavableEngine, Server, SessionManager, StandardInterfaceBuilder,
+    WrapInterfaceBuilder, io_exit, run_game,
+};
+use lexopt::{Arg, Parser, ValueExt};
+use std::fs::File;
+use std::io::{self, BufReader, IsTerminal, Write};
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::time::Duration;
 
-fn main() -> std::process::ExitCode {
-    io_exit(run_game(ReadlineInterfaceBuilder, game::Builder))
+const USAGE: &str = concat!(
+    "Usage: walk [<options>]\n",
+    "\n",
+    "Options:\n",
+    "  -i, --interface <readline|tui|plain|json>\n",
+    "                          Choose how to read commands [default: readline if\n",
+    "                          stdin is a terminal, plain otherwise]\n",
+    "  -l, --load <file>       Resume the game saved in <file>\n",
+    "  -s, --script <file>     Run the commands in <file> and exit\n",
+    "  -w, --wrap <width>      Wrap output to <width> columns (0 = no wrapping)\n",
+    "      --no-color          Don't use color\n",
+    "      --listen <addr>     Host a game for each client that connects to the\n",
+    "                          given TCP address (e.g., 127.0.0.1:4000)\n",
+    "      --listen-unix <path>\n",
+    "                          Host a game for each client that connects to the\n",
+    "                          given Unix socket\n",
+    "      --web <addr>        Serve a web page for playing in a browser at the\n",
+    "                          given TCP address (e.g., 127.0.0.1:8080)\n",
+    "      --telnet            Speak the Telnet protocol to clients\n",
+    "      --max-connections <n>\n",
+    "                          Maximum number of simultaneous clients [default: 16]\n",
+    "      --idle-timeout <secs>\n",
+    "                          Disconnect clients after <secs> seconds without\n",
+    "                          input; 0 = never [default: 600]\n",
+    "      --session-dir <dir>\n",
+    "                          Save the games of clients who have been idle for\n",
+    "                          five minutes to <dir> to free up memory\n",
+    "      --seed <n>          Set the random seed (currently only recorded in\n",
+    "                          save files, as the game has no random elements)\n",
+    "      --export-map <dot|mermaid>\n",
+    "                          Print a graph of the rooms and exit\n",
+    "  -h, --help              Show this help and exit\n",
+    "  -V, --version           Show the program version and exit",
+);
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Command {
+    Run(Options),
+    ExportMap(GraphFormat),
+    Help,
+    Version,
+}
+
+impl Command {
+    fn from_parser(mut parser: Parser) -> Result<Command, lexopt::Error> {
+        let mut opts = Options::default();
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Arg::Short('i') | Arg::Long("interface") => {
+                    opts.interface = match parser.value()?.string()?.as_str() {
+                        "readline" => InterfaceKind::Readline,
+                        #[cfg(feature = "tui")]
+                        "tui" => InterfaceKind::Tui,
+                        "plain" => InterfaceKind::Plain,
+                        "json" => InterfaceKind::Json,
+                        s => {
+                            return Err(lexopt::Error::UnexpectedValue {
+                                option: String::from("--interface"),
+                                value: s.into(),
+                            });
+                        }
+                    };
+                }
+                Arg::Short('l') | Arg::Long("load") => opts.load = Some(parser.value()?.into()),
+                Arg::Short('s') | Arg::Long("script") => {
+                    opts.script = Some(parser.value()?.into());
+                }
+                Arg::Short('w') | Arg::Long("wrap") => opts.wrap = parser.value()?.parse()?,
+                Arg::Long("no-color") => opts.color = false,
+                Arg::Long("listen") => opts.listen = Some(Listen::Tcp(parser.value()?.string()?)),
+                Arg::Long("listen-unix") => {
+                    opts.listen = Some(Listen::Unix(parser.value()?.into()));
+                }
+                #[cfg(feature = "web")]
+                Arg::Long("web") => opts.listen = Some(Listen::Web(parser.value()?.string()?)),
+                Arg::Long("telnet") => opts.telnet = true,
+                Arg::Long("max-connections") => opts.max_connections = parser.value()?.parse()?,
+                Arg::Long("idle-timeout") => opts.idle_timeout = parser.value()?.parse()?,
+                Arg::Long("session-dir") => opts.session_dir = Some(parser.value()?.into()),
+                Arg::Long("seed") => opts.seed = Some(parser.value()?.parse()?),
+                Arg::Long("export-map") => {
+                    let format = parser.value()?.parse_with(str::parse::<GraphFormat>)?;
+                    return Ok(Command::ExportMap(format));
+                }
+                Arg::Short('h') | Arg::Long("help") => return Ok(Command::Help),
+                Arg::Short('V') | Arg::Long("version") => return Ok(Command::Version),
+                _ => return Err(arg.unexpected()),
+            }
+        }
+        Ok(Command::Run(opts))
+    }
+
+    fn run(self) -> ExitCode {
+        match self {
+            Command::Run(opts) => opts.run(),
+            Command::ExportMap(format) => {
+                let graph = game::world_graph().render(format);
+                io_exit(writeln!(io::stdout().lock(), "{graph}"))
+            }
+            Command::Help => io_exit(writeln!(io::stdout().lock(), "{USAGE}")),
+            Command::Version => io_exit(writeln!(
+                io::stdout().lock(),
+                "walk {}",
+                env!("CARGO_PKG_VERSION")
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Options {
+    interface: InterfaceKind,
+    load: Option<PathBuf>,
+    script: Option<PathBuf>,
+    wrap: usize,
+    color: bool,
+    listen: Option<Listen>,
+    telnet: bool,
+    max_connections: usize,
+    // In seconds; 0 means no timeout
+    idle_timeout: u64,
+    session_dir: Option<PathBuf>,
+    seed: Option<u64>,
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options {
+            interface: InterfaceKind::Auto,
+            load: None,
+            script: None,
+            wrap: 0,
+            color: true,
+            listen: None,
+            telnet: false,
+            max_connections: 16,
+            idle_timeout: 600,
+            session_dir: None,
+            seed: None,
+        }
+    }
+}
+
+impl Options {
+    fn run(self) -> ExitCode {
+        let builder = game::Builder::default().seed(self.seed);
+        let restored = match self.load {
+            Some(path) => match game::Restored::load(&path) {
+                Ok(r) => Some(r.seed(self.seed)),
+                Err(e) => {
+                    eprintln!("walk: could not load {}: {e}", path.display());
+                    return ExitCode::from(2);
+                }
+            },
+            None => None,
+        };
+        if let Some(listen) = self.listen {
+            let server = Server::new()
+                .max_connections(self.max_connections)
+                .idle_timeout(
+                    (self.idle_timeout > 0).then(|| Duration::from_secs(self.idle_timeout)),
+                )
+                .wrap_width(self.wrap)
+                .telnet(self.telnet);
+            return io_exit(match restored {
+                Some(r) => host(listen, server, r, self.session_dir),
+                None => host(listen, server, builder, self.session_dir),
+            });
+        }
+        if let Some(path) = self.script {
+            let fp = match File::open(&path) {
+                Ok(fp) => fp,
+                Err(e) => {
+                    eprintln!("walk: could not open {}: {e}", path.display());
+                    return ExitCode::from(2);
+                }
+            };
+            let iface =
+                BasicInterfaceBuilder::new(BufReader::new(fp), io::stdout().lock()).echo(true);
+            return io_exit(play(iface, self.wrap, builder, restored));
+        }
+        let kind = match self.interface {
+            InterfaceKind::Auto if io::stdin().is_terminal() => InterfaceKind::Readline,
+            InterfaceKind::Auto => InterfaceKind::Plain,
+            kind => kind,
+        };
+        let color = self.color
+            && io::stdout().is_terminal()
+            && std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty());
+        let r = match kind {
+            InterfaceKind::Readline => {
+                let iface = ReadlineInterfaceBuilder::new().color(color);
+                play(iface, self.wrap, builder, restored)
+            }
+            #[cfg(feature = "tui")]
+            InterfaceKind::Tui => {
+                let iface = TuiInterfaceBuilder::new().color(color);
+                play(iface, self.wrap, builder, restored)
+            }
+            InterfaceKind::Json => {
+                let iface = JsonLinesInterfaceBuilder::new(io::stdin().lock(), io::stdout().lock());
+                play(iface, self.wrap, builder, restored)
+            }
+            _ => play(StandardInterfaceBuilder, self.wrap, builder, restored),
+        };
+        io_exit(r)
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Listen {
+    Tcp(String),
+    Unix(PathBuf),
+    #[cfg(feature = "web")]
+    Web(String),
+}
+
+impl Listen {
+    fn serve<G>(self, server: Server, game: G) -> io::Result<()>
+    where
+        G: GameBuilder + Clone + Send + 'static,
+    {
+        match self {
+            Listen::Tcp(addr) => {
+                let listener = TcpListener::bind(&addr)?;
+                eprintln!("walk: listening on {}", listener.local_addr()?);
+                server.serve_tcp(&listener, game)
+            }
+            #[cfg(feature = "web")]
+            Listen::Web(addr) => {
+                let listener = TcpListener::bind(&addr)?;
+                eprintln!("walk: serving http://{}/", listener.local_addr()?);
+                server.serve_web(&listener, game)
+            }
+            #[cfg(unix)]
+            Listen::Unix(path) => {
+                let listener = std::os::unix::net::UnixListener::bind(&path)?;
+                eprintln!("walk: listening on {}", path.display());
+                server.serve_unix(&listener, game)
+            }
+            #[cfg(not(unix))]
+            Listen::Unix(_) => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "Unix sockets are not supported on this platform",
+            )),
+        }
+    }
+}
+
+/// How often to check for idle sessions to evict when `--session-dir` is given
+const EVICT_INTERVAL: Duration = Duration::from_secs(60);
+
+// Host games for clients, keeping them in a `SessionManager` if
+// `session_dir` is given so that idle ones can be moved to disk
+fn host<G>(listen: Listen, server: Server, game: G, session_dir: Option<PathBuf>) -> io::Result<()>
+where
+    G: GameBuilder + Clone + Send + Sync + 'static,
+    G::Engine: SavableEngine + Send,
+{
+    let Some(dir) = session_dir else {
+        return listen.serve(server, game);
+    };
+    let manager = Arc::new(SessionManager::new(game, dir));
+    let evictor = Arc::clone(&manager);
+    std::thread::spawn(move || -> ! {
+        loop {
+            std::thread::sleep(EVICT_INTERVAL);
+            if let Err(e) = evictor.evict_idle() {
+                eprintln!("walk: could not save idle sessions: {e}");
+            }
+        }
+    });
+    listen.serve(server, manager.game())
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum InterfaceKind {
+    /// Use readline if stdin is a terminal, plain otherwise
+    Auto,
+    Readline,
+    /// Full-screen with a status line, falling back to readline
+    #[cfg(feature = "tui")]
+    Tui,
+    Plain,
+    /// JSON Lines for use by other programs
+    Json,
+}
+
+// Run a new or restored game on the given interface
+fn play<I: InterfaceBuilder>(
+    iface: I,
+    wrap: usize,
+    builder: game::Builder,
+    restored: Option<game::Restored>,
+) -> io::Result<()> {
+    let iface = WrapInterfaceBuilder::new(iface, wrap);
+    match restored {
+        Some(r) => run_game(iface, r),
+        None => run_game(iface, builder),
+    }
+}
+
+fn main() -> ExitCode {
+    match Command::from_parser(Parser::from_env()) {
+        Ok(cmd) => cmd.run(),
+        Err(e) => {
+            eprintln!("walk: {e}\n\n{USAGE}");
+            ExitCode::from(2)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Command, lexopt::Error> {
+        Command::from_parser(Parser::from_iter(
+            std::iter::once("walk").chain(args.iter().copied()),
+        ))
+    }
+
+    #[test]
+    fn parse_args() {
+        assert_eq!(parse(&[]).ok(), Some(Command::Run(Options::default())));
+        assert_eq!(
+            parse(&[
+                "-i",
+                "plain",
+                "--load",
+                "game.sav",
+                "--wrap=72",
+                "--no-color"
+            ])
+            .ok(),
+            Some(Command::Run(Options {
+                interface: InterfaceKind::Plain,
+                load: Some(PathBuf::from("game.sav")),
+                script: None,
+                wrap: 72,
+                color: false,
+                ..Options::default()
+            }))
+        );
+        assert_eq!(
+            parse(&["--seed", "42", "-s", "cmds.txt"]).ok(),
+            Some(Command::Run(Options {
+                script: Some(PathBuf::from("cmds.txt")),
+                seed: Some(42),
+                ..Options::default()
+            }))
+        );
+        assert_eq!(
+            parse(&["--export-map", "mermaid"]).ok(),
+            Some(Command::ExportMap(GraphFormat::Mermaid))
+        );
+        assert_eq!(parse(&["-V"]).ok(), Some(Command::Version));
+        assert_eq!(
+            parse(&["--interface=json"]).ok(),
+            Some(Command::Run(Options {
+                interface: InterfaceKind::Json,
+                ..Options::default()
+            }))
+        );
+        #[cfg(feature = "tui")]
+        assert_eq!(
+            parse(&["-itui"]).ok(),
+            Some(Command::Run(Options {
+                interface: InterfaceKind::Tui,
+                ..Options::default()
+            }))
+        );
+        assert_eq!(
+            parse(&[
+                "--listen",
+                "127.0.0.1:4000",
+                "--telnet",
+                "--max-connections",
+                "3",
+                "--idle-timeout",
+                "0",
+                "--session-dir",
+                "sessions"
+            ])
+            .ok(),
+            Some(Command::Run(Options {
+                listen: Some(Listen::Tcp(String::from("127.0.0.1:4000"))),
+                telnet: true,
+                max_connections: 3,
+                idle_timeout: 0,
+                session_dir: Some(PathBuf::from("sessions")),
+                ..Options::default()
+            }))
+        );
+        #[cfg(feature = "web")]
+        assert_eq!(
+            parse(&["--web", "127.0.0.1:8080"]).ok(),
+            Some(Command::Run(Options {
+                listen: Some(Listen::Web(String::from("127.0.0.1:8080"))),
+                ..Options::default()
+            }))
+        );
+        assert!(parse(&["--interface", "gui"]).is_err());
+        assert!(parse(&["--seed", "lots"]).is_err());
+        assert!(parse(&["--export-map", "svg"]).is_err());
+        assert!(parse(&["extra"]).is_err());
+    }
 }